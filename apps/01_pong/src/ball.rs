@@ -1,6 +1,7 @@
 use crate::asset_tracking::LoadResource;
+use crate::practice::ShotFinished;
 use crate::scene::{Goal, Ground};
-use crate::{AppState, Score, ScoreChanged};
+use crate::{AppState, GameMode, Score, ScoreChanged};
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use rand::prelude::*;
@@ -8,12 +9,18 @@ use std::f32::consts::PI;
 
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<BallAssets>();
-    app.add_systems(OnEnter(AppState::Waiting), spawn_ball);
-    app.add_systems(OnEnter(AppState::Match), change_velocity);
+    app.add_systems(
+        OnEnter(AppState::Waiting),
+        spawn_ball.run_if(not(resource_equals(GameMode::Practice))),
+    );
+    app.add_systems(
+        OnEnter(AppState::Match),
+        change_velocity.run_if(not(resource_equals(GameMode::Practice))),
+    );
 }
 
 #[derive(Resource, Asset, TypePath, Clone)]
-struct BallAssets {
+pub struct BallAssets {
     #[dependency]
     ball_texture: Handle<Image>,
 }
@@ -28,34 +35,55 @@ impl FromWorld for BallAssets {
 }
 
 #[derive(Component)]
-struct Ball;
+pub struct Ball;
 
 fn spawn_ball(mut commands: Commands, ball_assets: Res<BallAssets>) {
+    commands
+        .spawn(ball_bundle(&ball_assets))
+        .observe(on_ball_collision);
+}
+
+/// Spawns a ball that is already moving, e.g. one shot by the ball machine.
+pub fn fire_ball(
+    commands: &mut Commands,
+    ball_assets: &BallAssets,
+    position: Vec2,
+    velocity: Vec2,
+) {
     commands
         .spawn((
-            Ball,
-            Sprite {
-                image: ball_assets.ball_texture.clone(),
-                custom_size: Some(Vec2::new(20.0, 20.0)),
-                ..default()
-            },
-            RigidBody::Dynamic,
-            Collider::circle(9.0),
-            DespawnOnExit(AppState::Match),
-            DespawnOnEnter(AppState::Menu),
-            Restitution::new(1.0).with_combine_rule(CoefficientCombine::Max),
-            Friction::new(0.0).with_combine_rule(CoefficientCombine::Min),
-            CollisionEventsEnabled,
+            ball_bundle(ball_assets),
+            Position(position),
+            LinearVelocity(velocity),
         ))
         .observe(on_ball_collision);
 }
 
+fn ball_bundle(ball_assets: &BallAssets) -> impl Bundle {
+    (
+        Ball,
+        Sprite {
+            image: ball_assets.ball_texture.clone(),
+            custom_size: Some(Vec2::new(20.0, 20.0)),
+            ..default()
+        },
+        RigidBody::Dynamic,
+        Collider::circle(9.0),
+        DespawnOnExit(AppState::Match),
+        DespawnOnEnter(AppState::Menu),
+        Restitution::new(1.0).with_combine_rule(CoefficientCombine::Max),
+        Friction::new(0.0).with_combine_rule(CoefficientCombine::Min),
+        CollisionEventsEnabled,
+    )
+}
+
 fn on_ball_collision(
     event: On<CollisionStart>,
     mut commands: Commands,
     ground_query: Query<&Ground>,
     goal_query: Query<&Goal>,
     mut score: ResMut<Score>,
+    game_mode: Res<GameMode>,
 ) {
     let colliding_entity = event.collider2;
 
@@ -66,6 +94,14 @@ fn on_ball_collision(
 
     if let Ok(goal) = goal_query.get(colliding_entity) {
         println!("Ball touched the goal");
+        if *game_mode == GameMode::Practice {
+            commands.trigger(ShotFinished {
+                ball: event.collider1,
+                returned: !goal.is_left(),
+            });
+            return;
+        }
+
         if goal.is_left() {
             score.right += 1;
         } else {
@@ -87,5 +123,9 @@ fn change_velocity(mut velocity: Single<&mut LinearVelocity, With<Ball>>) {
         rng.random_range(3.0 * PI / 4.0..5.0 * PI / 4.0)
     };
 
-    velocity.0 = Vec2::new(angle.cos(), angle.sin()) * speed;
+    velocity.0 = launch_velocity(angle, speed);
+}
+
+pub fn launch_velocity(angle: f32, speed: f32) -> Vec2 {
+    Vec2::new(angle.cos(), angle.sin()) * speed
 }
@@ -1,17 +1,56 @@
-use crate::paddle::Paddle;
-use crate::{AppState, MenuTimer};
-use avian2d::prelude::LinearVelocity;
+use crate::paddle::{Paddle, PaddleConfig, PaddleSettings, PaddleSide};
+use crate::practice::BallMachine;
+use crate::{
+    AppState, AppSystems, GameMode, MatchPhase, MenuScreen, ReplayFinished, WaitTimer, start_game,
+};
+use avian2d::prelude::{LinearVelocity, Position};
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 use bevy_enhanced_input::EnhancedInputPlugin;
-use bevy_enhanced_input::prelude::{Fire, InputAction, InputContextAppExt};
+use bevy_enhanced_input::actions;
+use bevy_enhanced_input::prelude::*;
+
+/// How fast a dragged paddle catches up with the pointer (velocity per unit of distance).
+const POINTER_GAIN: f32 = 12.0;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(EnhancedInputPlugin);
     app.add_input_context::<Paddle>();
     app.add_input_context::<Camera>();
+    app.add_input_context::<MenuControls>();
     app.add_observer(apply_left_paddle_movement);
     app.add_observer(apply_right_paddle_movement);
+    app.add_observer(stop_left_paddle_movement);
+    app.add_observer(stop_right_paddle_movement);
     app.add_observer(apply_restart);
+    app.add_observer(apply_skip_replay);
+    app.add_observer(apply_select_versus);
+    app.add_observer(apply_select_practice);
+    app.add_observer(apply_select_tournament);
+    app.add_observer(apply_machine_speed);
+    app.add_observer(apply_machine_angle);
+    app.add_observer(apply_machine_interval);
+    app.add_observer(apply_switch_paddle_side);
+    app.add_observer(apply_paddle_length);
+    app.add_observer(apply_paddle_speed);
+    app.add_observer(apply_paddle_responsiveness);
+    app.add_observer(apply_starting_score);
+
+    app.add_systems(OnEnter(MenuScreen::Main), spawn_menu_controls);
+
+    app.add_systems(
+        Update,
+        record_pointer_targets
+            .run_if(in_state(AppState::Waiting).or(in_state(MatchPhase::Playing)))
+            .in_set(AppSystems::RecordInput),
+    );
+    app.add_systems(
+        Update,
+        (apply_pointer_targets, steer_paddles)
+            .chain()
+            .run_if(in_state(AppState::Waiting).or(in_state(MatchPhase::Playing)))
+            .in_set(AppSystems::Update),
+    );
 }
 
 #[derive(InputAction)]
@@ -26,29 +65,295 @@ pub struct RightPaddleMovement;
 #[action_output(bool)]
 pub struct RestartAction;
 
+#[derive(InputAction)]
+#[action_output(bool)]
+pub struct SkipReplayAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct SelectVersusAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct SelectPracticeAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct SelectTournamentAction;
+
+#[derive(InputAction)]
+#[action_output(f32)]
+struct MachineSpeedAction;
+
+#[derive(InputAction)]
+#[action_output(f32)]
+struct MachineAngleAction;
+
+#[derive(InputAction)]
+#[action_output(f32)]
+struct MachineIntervalAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct SwitchPaddleSideAction;
+
+#[derive(InputAction)]
+#[action_output(f32)]
+struct PaddleLengthAction;
+
+#[derive(InputAction)]
+#[action_output(f32)]
+struct PaddleSpeedAction;
+
+#[derive(InputAction)]
+#[action_output(f32)]
+struct PaddleResponsivenessAction;
+
+#[derive(InputAction)]
+#[action_output(f32)]
+struct StartingScoreAction;
+
+/// Input context that only exists while the main menu is shown.
+#[derive(Component)]
+struct MenuControls;
+
+/// World y the paddle is being dragged to by a mouse or touch pointer, if any.
+#[derive(Component, Default, PartialEq)]
+pub struct PointerTarget(pub Option<f32>);
+
+/// Vertical velocity the paddle eases towards, set by the keys or a pointer.
+#[derive(Component, Default)]
+pub struct TargetVelocity(pub f32);
+
 fn apply_left_paddle_movement(
     movement: On<Fire<LeftPaddleMovement>>,
-    mut paddles: Query<&mut LinearVelocity, With<Paddle>>,
+    mut paddles: Query<&mut TargetVelocity, With<Paddle>>,
 ) {
-    if let Ok(mut velocity) = paddles.get_mut(movement.context) {
-        velocity.y = movement.value;
+    if let Ok(mut target) = paddles.get_mut(movement.context) {
+        target.0 = movement.value;
     }
 }
 
 fn apply_right_paddle_movement(
     movement: On<Fire<RightPaddleMovement>>,
-    mut paddles: Query<&mut LinearVelocity, With<Paddle>>,
+    mut paddles: Query<&mut TargetVelocity, With<Paddle>>,
+) {
+    if let Ok(mut target) = paddles.get_mut(movement.context) {
+        target.0 = movement.value;
+    }
+}
+
+fn stop_left_paddle_movement(
+    movement: On<Complete<LeftPaddleMovement>>,
+    mut paddles: Query<&mut TargetVelocity, With<Paddle>>,
+) {
+    if let Ok(mut target) = paddles.get_mut(movement.context) {
+        target.0 = 0.0;
+    }
+}
+
+fn stop_right_paddle_movement(
+    movement: On<Complete<RightPaddleMovement>>,
+    mut paddles: Query<&mut TargetVelocity, With<Paddle>>,
 ) {
-    if let Ok(mut velocity) = paddles.get_mut(movement.context) {
-        velocity.y = movement.value;
+    if let Ok(mut target) = paddles.get_mut(movement.context) {
+        target.0 = 0.0;
     }
 }
 
-fn apply_restart(
-    _event: On<Fire<RestartAction>>,
-    mut next_state: ResMut<NextState<AppState>>,
-    mut timer: ResMut<MenuTimer>,
+/// Every touch (and the mouse while the left button is held) drives the paddle on its half
+/// of the screen, so two players can share one touch screen.
+fn record_pointer_targets(
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform)>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut paddles: Query<(&PaddleSide, &mut PointerTarget)>,
 ) {
-    timer.0.reset();
+    let (camera, camera_transform) = *camera;
+    let half_width = window.width() / 2.0;
+
+    let cursor = window
+        .cursor_position()
+        .filter(|_| mouse.pressed(MouseButton::Left));
+
+    let mut left_target = None;
+    let mut right_target = None;
+
+    for screen_pos in touches.iter().map(|touch| touch.position()).chain(cursor) {
+        let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, screen_pos) else {
+            continue;
+        };
+
+        if screen_pos.x < half_width {
+            left_target = Some(world_pos.y);
+        } else {
+            right_target = Some(world_pos.y);
+        }
+    }
+
+    for (side, mut target) in &mut paddles {
+        let y = match side {
+            PaddleSide::Left => left_target,
+            PaddleSide::Right => right_target,
+        };
+        target.set_if_neq(PointerTarget(y));
+    }
+}
+
+fn apply_pointer_targets(
+    mut paddles: Query<
+        (
+            Ref<PointerTarget>,
+            &PaddleSettings,
+            &Position,
+            &mut TargetVelocity,
+        ),
+        With<Paddle>,
+    >,
+) {
+    for (pointer, settings, position, mut target) in &mut paddles {
+        match pointer.0 {
+            Some(y) => {
+                target.0 = ((y - position.y) * POINTER_GAIN).clamp(-settings.speed, settings.speed);
+            }
+            // Stop the paddle once, when the pointer is released.
+            None if pointer.is_changed() => target.0 = 0.0,
+            None => (),
+        }
+    }
+}
+
+/// Keys and pointers only set the target velocity, so both ease into it at the
+/// paddle's responsiveness.
+fn steer_paddles(
+    time: Res<Time>,
+    mut paddles: Query<(&TargetVelocity, &PaddleSettings, &mut LinearVelocity), With<Paddle>>,
+) {
+    for (target, settings, mut velocity) in &mut paddles {
+        settings.steer(&mut velocity.y, target.0, time.delta_secs());
+    }
+}
+
+fn apply_restart(_event: On<Fire<RestartAction>>, mut next_state: ResMut<NextState<AppState>>) {
     next_state.set(AppState::Menu);
 }
+
+fn apply_skip_replay(
+    _event: On<Start<SkipReplayAction>>,
+    mut commands: Commands,
+    phase: Option<Res<State<MatchPhase>>>,
+) {
+    if phase.is_some_and(|phase| *phase.get() == MatchPhase::Replay) {
+        commands.trigger(ReplayFinished);
+    }
+}
+
+fn spawn_menu_controls(mut commands: Commands) {
+    commands.spawn((
+        MenuControls,
+        DespawnOnExit(MenuScreen::Main),
+        actions!(
+            MenuControls[
+                (Action::<SelectVersusAction>::new(), bindings![KeyCode::Digit1]),
+                (Action::<SelectPracticeAction>::new(), bindings![KeyCode::Digit2]),
+                (Action::<SelectTournamentAction>::new(), bindings![KeyCode::Digit3]),
+                (
+                    Action::<MachineSpeedAction>::new(),
+                    Bindings::spawn(Bidirectional::new(KeyCode::KeyQ, KeyCode::KeyA))
+                ),
+                (
+                    Action::<MachineAngleAction>::new(),
+                    Bindings::spawn(Bidirectional::new(KeyCode::KeyW, KeyCode::KeyS))
+                ),
+                (
+                    Action::<MachineIntervalAction>::new(),
+                    Bindings::spawn(Bidirectional::new(KeyCode::KeyE, KeyCode::KeyD))
+                ),
+                (Action::<SwitchPaddleSideAction>::new(), bindings![KeyCode::Tab]),
+                (
+                    Action::<PaddleLengthAction>::new(),
+                    Bindings::spawn(Bidirectional::new(KeyCode::KeyT, KeyCode::KeyG))
+                ),
+                (
+                    Action::<PaddleSpeedAction>::new(),
+                    Bindings::spawn(Bidirectional::new(KeyCode::KeyY, KeyCode::KeyH))
+                ),
+                (
+                    Action::<PaddleResponsivenessAction>::new(),
+                    Bindings::spawn(Bidirectional::new(KeyCode::KeyU, KeyCode::KeyJ))
+                ),
+                (
+                    Action::<StartingScoreAction>::new(),
+                    Bindings::spawn(Bidirectional::new(KeyCode::KeyI, KeyCode::KeyK))
+                ),
+            ]
+        ),
+    ));
+}
+
+fn apply_select_versus(
+    _event: On<Start<SelectVersusAction>>,
+    game_mode: ResMut<GameMode>,
+    timer: ResMut<WaitTimer>,
+    next_state: ResMut<NextState<AppState>>,
+) {
+    start_game(GameMode::Versus, game_mode, timer, next_state);
+}
+
+fn apply_select_practice(
+    _event: On<Start<SelectPracticeAction>>,
+    game_mode: ResMut<GameMode>,
+    timer: ResMut<WaitTimer>,
+    next_state: ResMut<NextState<AppState>>,
+) {
+    start_game(GameMode::Practice, game_mode, timer, next_state);
+}
+
+fn apply_select_tournament(
+    _event: On<Start<SelectTournamentAction>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    next_screen.set(MenuScreen::Tournament);
+}
+
+fn apply_machine_speed(event: On<Start<MachineSpeedAction>>, mut machine: ResMut<BallMachine>) {
+    machine.adjust_speed(event.value);
+}
+
+fn apply_machine_angle(event: On<Start<MachineAngleAction>>, mut machine: ResMut<BallMachine>) {
+    machine.adjust_angle_range(event.value);
+}
+
+fn apply_machine_interval(
+    event: On<Start<MachineIntervalAction>>,
+    mut machine: ResMut<BallMachine>,
+) {
+    machine.adjust_interval(event.value);
+}
+
+fn apply_switch_paddle_side(
+    _event: On<Start<SwitchPaddleSideAction>>,
+    mut config: ResMut<PaddleConfig>,
+) {
+    config.switch_side();
+}
+
+fn apply_paddle_length(event: On<Start<PaddleLengthAction>>, mut config: ResMut<PaddleConfig>) {
+    config.selected_mut().adjust_length(event.value);
+}
+
+fn apply_paddle_speed(event: On<Start<PaddleSpeedAction>>, mut config: ResMut<PaddleConfig>) {
+    config.selected_mut().adjust_speed(event.value);
+}
+
+fn apply_paddle_responsiveness(
+    event: On<Start<PaddleResponsivenessAction>>,
+    mut config: ResMut<PaddleConfig>,
+) {
+    config.selected_mut().adjust_responsiveness(event.value);
+}
+
+fn apply_starting_score(event: On<Start<StartingScoreAction>>, mut config: ResMut<PaddleConfig>) {
+    config.selected_mut().adjust_starting_score(event.value);
+}
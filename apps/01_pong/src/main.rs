@@ -7,13 +7,17 @@ mod asset_tracking;
 mod ball;
 mod input;
 mod paddle;
+mod practice;
+mod replay;
 mod scene;
+mod tournament;
 mod ui;
 
 use crate::asset_tracking::{ResourceHandles, ResourceLoadState};
-use crate::input::RestartAction;
-use avian2d::prelude::Gravity;
+use crate::input::{RestartAction, SkipReplayAction};
+use crate::paddle::PaddleConfig;
 use avian2d::PhysicsPlugins;
+use avian2d::prelude::Gravity;
 use bevy::asset::AssetMetaCheck;
 use bevy::color::palettes::tailwind::BLUE_900;
 use bevy::prelude::*;
@@ -61,10 +65,12 @@ impl Plugin for AppPlugin {
         app.insert_resource(ClearColor(BLUE_900.into()));
         app.insert_resource(Gravity(Vec2::ZERO));
         app.init_state::<AppState>();
+        app.add_sub_state::<MenuScreen>();
+        app.add_sub_state::<MatchPhase>();
         app.init_state::<AssetState>();
         app.init_resource::<Score>();
+        app.init_resource::<GameMode>();
         app.init_resource::<WaitTimer>();
-        app.init_resource::<MenuTimer>();
         app.add_observer(start_waiting);
 
         app.add_plugins((
@@ -72,7 +78,10 @@ impl Plugin for AppPlugin {
             input::plugin,
             paddle::plugin,
             ball::plugin,
+            practice::plugin,
+            replay::plugin,
             scene::plugin,
+            tournament::plugin,
             ui::plugin,
         ));
 
@@ -93,14 +102,8 @@ impl Plugin for AppPlugin {
             Update,
             check_assets.run_if(in_state(AppState::Loading).and(in_state(AssetState::Loading))),
         );
-        app.add_systems(OnEnter(AppState::Menu), reset_score);
+        app.add_systems(OnExit(AppState::Menu), reset_score);
         app.add_systems(OnEnter(AssetState::Done), enter_menu);
-        app.add_systems(
-            Update,
-            tick_menu_timer
-                .run_if(in_state(AppState::Menu))
-                .in_set(AppSystems::TickTimers),
-        );
         app.add_systems(
             Update,
             tick_waiting_timer
@@ -132,6 +135,22 @@ enum AppState {
     Match,
 }
 
+#[derive(SubStates, Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[source(AppState = AppState::Menu)]
+enum MenuScreen {
+    #[default]
+    Main,
+    Tournament,
+}
+
+#[derive(SubStates, Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[source(AppState = AppState::Match)]
+enum MatchPhase {
+    #[default]
+    Playing,
+    Replay,
+}
+
 #[derive(States, Debug, Default, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 enum AssetState {
     #[default]
@@ -146,9 +165,22 @@ struct Score {
     right: u32,
 }
 
+#[derive(Resource, Default, Debug, Clone, Copy, Eq, PartialEq)]
+enum GameMode {
+    #[default]
+    Versus,
+    /// A single player returns balls fired by the ball machine.
+    Practice,
+    /// A versus match that is part of a [`tournament::Tournament`].
+    Tournament,
+}
+
 #[derive(Event)]
 struct ScoreChanged;
 
+#[derive(Event)]
+struct ReplayFinished;
+
 #[derive(Resource)]
 struct WaitTimer(Timer);
 
@@ -158,29 +190,35 @@ impl Default for WaitTimer {
     }
 }
 
-#[derive(Resource)]
-struct MenuTimer(Timer);
-
-impl Default for MenuTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(0.5, TimerMode::Once))
-    }
-}
-
 fn setup_camera(mut commands: Commands) {
     commands.spawn((
         Camera2d,
-        actions!(Camera[(Action::<RestartAction>::new(), bindings![KeyCode::KeyR])]),
+        actions!(
+            Camera[
+                (Action::<RestartAction>::new(), bindings![KeyCode::KeyR]),
+                (Action::<SkipReplayAction>::new(), bindings![KeyCode::Space]),
+            ]
+        ),
     ));
 }
 
-fn enter_menu(mut next_state: ResMut<NextState<AppState>>, mut timer: ResMut<MenuTimer>) {
-    timer.0.reset();
+fn enter_menu(mut next_state: ResMut<NextState<AppState>>) {
     next_state.set(AppState::Menu);
 }
 
+fn start_game(
+    mode: GameMode,
+    mut game_mode: ResMut<GameMode>,
+    mut timer: ResMut<WaitTimer>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    *game_mode = mode;
+    timer.0.reset();
+    next_state.set(AppState::Waiting);
+}
+
 fn start_waiting(
-    _event: On<ScoreChanged>,
+    _event: On<ReplayFinished>,
     mut next_state: ResMut<NextState<AppState>>,
     mut timer: ResMut<WaitTimer>,
 ) {
@@ -200,23 +238,16 @@ fn tick_waiting_timer(
     }
 }
 
-fn tick_menu_timer(
-    time: Res<Time>,
-    mut menu_timer: ResMut<MenuTimer>,
-    mut wait_timer: ResMut<WaitTimer>,
-    mut next_state: ResMut<NextState<AppState>>,
-) {
-    menu_timer.0.tick(time.delta());
-
-    if menu_timer.0.just_finished() {
-        wait_timer.0.reset();
-        next_state.set(AppState::Waiting);
+/// Tournament matches ignore the starting scores, so the bracket only records
+/// goals scored in the match.
+fn reset_score(mut score: ResMut<Score>, config: Res<PaddleConfig>, game_mode: Res<GameMode>) {
+    if *game_mode == GameMode::Tournament {
+        *score = Score::default();
+        return;
     }
-}
 
-fn reset_score(mut score: ResMut<Score>) {
-    score.left = 0;
-    score.right = 0;
+    score.left = config.left.starting_score;
+    score.right = config.right.starting_score;
 }
 
 fn check_assets(
@@ -1,17 +1,20 @@
 use crate::asset_tracking::LoadResource;
-use crate::input::{LeftPaddleMovement, RightPaddleMovement};
-use crate::{AppState, WORLD_WIDTH};
+use crate::input::{LeftPaddleMovement, PointerTarget, RightPaddleMovement, TargetVelocity};
+use crate::{AppState, GameMode, WORLD_WIDTH};
 use avian2d::prelude::*;
 use bevy::prelude::*;
 use bevy_enhanced_input::action::Action;
 use bevy_enhanced_input::actions;
-use bevy_enhanced_input::prelude::{Bidirectional, Bindings, Scale, SmoothNudge};
+use bevy_enhanced_input::prelude::{Bidirectional, Bindings, Scale};
 
+/// Paddle sprite width, and the default paddle length.
 const PADDLE_SIZE: f32 = 150.0;
 pub const PADDLE_SPEED: f32 = 800.0;
+const PADDLE_RESPONSIVENESS: f32 = 20.0;
 
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<PaddleAssets>();
+    app.init_resource::<PaddleConfig>();
     app.add_systems(
         OnTransition {
             exited: AppState::Menu,
@@ -39,60 +42,169 @@ impl FromWorld for PaddleAssets {
 #[derive(Component)]
 pub struct Paddle;
 
-fn spawn_paddles(mut commands: Commands, paddle_assets: Res<PaddleAssets>) {
-    let paddle_colliders = vec![
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PaddleSide {
+    Left,
+    Right,
+}
+
+/// Per-side handicap settings, also kept on the spawned paddle.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct PaddleSettings {
+    pub length: f32,
+    pub speed: f32,
+    /// How quickly the paddle eases into its target velocity, higher values react faster.
+    pub responsiveness: f32,
+    pub starting_score: u32,
+}
+
+impl Default for PaddleSettings {
+    fn default() -> Self {
+        Self {
+            length: PADDLE_SIZE,
+            speed: PADDLE_SPEED,
+            responsiveness: PADDLE_RESPONSIVENESS,
+            starting_score: 0,
+        }
+    }
+}
+
+impl PaddleSettings {
+    pub fn adjust_length(&mut self, steps: f32) {
+        self.length = (self.length + steps * 25.0).clamp(75.0, 300.0);
+    }
+
+    pub fn adjust_speed(&mut self, steps: f32) {
+        self.speed = (self.speed + steps * 100.0).clamp(300.0, 1500.0);
+    }
+
+    pub fn adjust_responsiveness(&mut self, steps: f32) {
+        self.responsiveness = (self.responsiveness + steps * 5.0).clamp(5.0, 50.0);
+    }
+
+    pub fn adjust_starting_score(&mut self, steps: f32) {
+        self.starting_score = (self.starting_score as i32 + steps as i32).clamp(0, 9) as u32;
+    }
+
+    /// Eases `velocity` towards `target` at this paddle's responsiveness.
+    pub fn steer(&self, velocity: &mut f32, target: f32, delta: f32) {
+        velocity.smooth_nudge(&target, self.responsiveness, delta);
+    }
+}
+
+/// Paddle settings chosen in the menu.
+#[derive(Resource, Debug)]
+pub struct PaddleConfig {
+    pub left: PaddleSettings,
+    pub right: PaddleSettings,
+    /// Side currently edited in the menu.
+    pub selected: PaddleSide,
+}
+
+impl Default for PaddleConfig {
+    fn default() -> Self {
+        Self {
+            left: PaddleSettings::default(),
+            right: PaddleSettings::default(),
+            selected: PaddleSide::Left,
+        }
+    }
+}
+
+impl PaddleConfig {
+    pub fn selected_mut(&mut self) -> &mut PaddleSettings {
+        match self.selected {
+            PaddleSide::Left => &mut self.left,
+            PaddleSide::Right => &mut self.right,
+        }
+    }
+
+    pub fn switch_side(&mut self) {
+        self.selected = match self.selected {
+            PaddleSide::Left => PaddleSide::Right,
+            PaddleSide::Right => PaddleSide::Left,
+        };
+    }
+}
+
+/// The collider follows the shape of the paddle texture, so offsets scale with the length.
+fn paddle_colliders(length: f32) -> Vec<(Position, Rotation, Collider)> {
+    let radius = PADDLE_SIZE.min(length) * 0.23;
+    let capsule_length = (length * 0.61 - 2.0 * radius).max(0.0);
+
+    vec![
         (
-            Position::from_xy(0.0, -(PADDLE_SIZE * 0.32)),
+            Position::from_xy(0.0, -(length * 0.32)),
             Rotation::default(),
-            Collider::rectangle(PADDLE_SIZE * 0.1, PADDLE_SIZE * 0.3),
+            Collider::rectangle(PADDLE_SIZE * 0.1, length * 0.3),
         ),
         (
-            Position::from_xy(0.0, PADDLE_SIZE * 0.17),
+            Position::from_xy(0.0, length * 0.17),
             Rotation::default(),
-            Collider::capsule(PADDLE_SIZE * 0.23, PADDLE_SIZE * 0.15),
+            Collider::capsule(radius, capsule_length),
         ),
-    ];
+    ]
+}
 
+fn spawn_paddles(
+    mut commands: Commands,
+    paddle_assets: Res<PaddleAssets>,
+    game_mode: Res<GameMode>,
+    config: Res<PaddleConfig>,
+) {
+    let left = config.left;
     commands.spawn((
         Paddle,
+        PaddleSide::Left,
+        left,
+        PointerTarget::default(),
+        TargetVelocity::default(),
         Sprite {
             image: paddle_assets.paddle_texture.clone(),
-            custom_size: Some(Vec2::new(PADDLE_SIZE, PADDLE_SIZE)),
+            custom_size: Some(Vec2::new(PADDLE_SIZE, left.length)),
             ..default()
         },
         Position::from_xy(-((WORLD_WIDTH / 2) as f32) + PADDLE_SIZE / 2.0, 0.0),
         RigidBody::Dynamic,
-        Collider::compound(paddle_colliders.clone()),
+        Collider::compound(paddle_colliders(left.length)),
         DespawnOnEnter(AppState::Menu),
         LockedAxes::ROTATION_LOCKED.lock_translation_x(),
         actions!(
             Paddle[(
                 Action::<LeftPaddleMovement>::new(),
-                Scale::splat(PADDLE_SPEED),
-                SmoothNudge::new(20.0),
+                Scale::splat(left.speed),
                 Bindings::spawn(Bidirectional::new(KeyCode::KeyW, KeyCode::KeyS))
             )]
         ),
     ));
 
+    // The ball machine takes the right side in practice mode.
+    if *game_mode == GameMode::Practice {
+        return;
+    }
+
+    let right = config.right;
     commands.spawn((
         Paddle,
+        PaddleSide::Right,
+        right,
+        PointerTarget::default(),
+        TargetVelocity::default(),
         Sprite {
             image: paddle_assets.paddle_texture.clone(),
-            custom_size: Some(Vec2::new(PADDLE_SIZE, PADDLE_SIZE)),
+            custom_size: Some(Vec2::new(PADDLE_SIZE, right.length)),
             flip_x: true,
             ..default()
         },
         Position::from_xy((WORLD_WIDTH / 2) as f32 - PADDLE_SIZE / 2.0, 0.0),
         RigidBody::Dynamic,
-        Collider::compound(paddle_colliders),
+        Collider::compound(paddle_colliders(right.length)),
         DespawnOnEnter(AppState::Menu),
         LockedAxes::ROTATION_LOCKED.lock_translation_x(),
         actions!(
             Paddle[(
                 Action::<RightPaddleMovement>::new(),
-                Scale::splat(PADDLE_SPEED),
-                SmoothNudge::new(20.0),
+                Scale::splat(right.speed),
                 Bindings::spawn(Bidirectional::new(KeyCode::ArrowUp, KeyCode::ArrowDown))
             )]
         ),
@@ -0,0 +1,196 @@
+//! Practice mode: a ball machine on the right side fires balls at the left paddle.
+
+use crate::ball::{Ball, BallAssets, fire_ball, launch_velocity};
+use crate::{AppState, AppSystems, GameMode, WORLD_WIDTH};
+use avian2d::prelude::*;
+use bevy::color::palettes::tailwind::GRAY_400;
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+const MACHINE_SIZE: Vec2 = Vec2::new(40.0, 80.0);
+const MACHINE_POSITION: Vec2 = Vec2::new((WORLD_WIDTH / 2) as f32 - 60.0, 0.0);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<BallMachine>();
+    app.init_resource::<PracticeStats>();
+    app.init_resource::<MachineTimer>();
+    app.add_observer(on_shot_finished);
+
+    app.add_systems(
+        OnTransition {
+            exited: AppState::Menu,
+            entered: AppState::Waiting,
+        },
+        (spawn_machine, reset_practice).run_if(resource_equals(GameMode::Practice)),
+    );
+    app.add_systems(
+        Update,
+        tick_machine_timer
+            .run_if(in_state(AppState::Match).and(resource_equals(GameMode::Practice)))
+            .in_set(AppSystems::TickTimers),
+    );
+    app.add_systems(
+        Update,
+        fire_machine
+            .run_if(in_state(AppState::Match).and(resource_equals(GameMode::Practice)))
+            .in_set(AppSystems::Update),
+    );
+}
+
+/// Ball machine settings, adjusted in the menu.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct BallMachine {
+    pub speed: f32,
+    /// Maximum deviation from a horizontal shot, in degrees.
+    pub angle_range: f32,
+    /// Delay between the end of one shot and the next one, in seconds.
+    pub interval: f32,
+}
+
+impl Default for BallMachine {
+    fn default() -> Self {
+        Self {
+            speed: 500.0,
+            angle_range: 30.0,
+            interval: 1.0,
+        }
+    }
+}
+
+impl BallMachine {
+    pub fn adjust_speed(&mut self, steps: f32) {
+        self.speed = (self.speed + steps * 100.0).clamp(200.0, 1200.0);
+    }
+
+    pub fn adjust_angle_range(&mut self, steps: f32) {
+        self.angle_range = (self.angle_range + steps * 5.0).clamp(0.0, 60.0);
+    }
+
+    pub fn adjust_interval(&mut self, steps: f32) {
+        self.interval = (self.interval + steps * 0.25).clamp(0.25, 3.0);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShotResult {
+    Missed,
+    /// The ball got back past the machine, `offset` away from its center.
+    Returned {
+        offset: f32,
+    },
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct PracticeStats(pub Vec<ShotResult>);
+
+impl PracticeStats {
+    pub fn returned(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|shot| matches!(shot, ShotResult::Returned { .. }))
+            .count()
+    }
+
+    pub fn average_offset(&self) -> Option<f32> {
+        let offsets: Vec<f32> = self
+            .0
+            .iter()
+            .filter_map(|shot| match shot {
+                ShotResult::Returned { offset } => Some(*offset),
+                ShotResult::Missed => None,
+            })
+            .collect();
+
+        (!offsets.is_empty()).then(|| offsets.iter().sum::<f32>() / offsets.len() as f32)
+    }
+}
+
+/// Sent instead of [`crate::ScoreChanged`] when a practice ball reaches a goal.
+#[derive(Event)]
+pub struct ShotFinished {
+    pub ball: Entity,
+    pub returned: bool,
+}
+
+#[derive(Component)]
+struct Machine;
+
+#[derive(Resource, Default)]
+struct MachineTimer(Timer);
+
+fn spawn_machine(mut commands: Commands) {
+    commands.spawn((
+        Machine,
+        Sprite::from_color(GRAY_400, MACHINE_SIZE),
+        Transform::from_translation(MACHINE_POSITION.extend(0.0)),
+        DespawnOnEnter(AppState::Menu),
+    ));
+}
+
+fn reset_practice(
+    machine: Res<BallMachine>,
+    mut stats: ResMut<PracticeStats>,
+    mut timer: ResMut<MachineTimer>,
+) {
+    stats.0.clear();
+    timer.0 = Timer::from_seconds(machine.interval, TimerMode::Once);
+}
+
+fn tick_machine_timer(time: Res<Time>, mut timer: ResMut<MachineTimer>) {
+    timer.0.tick(time.delta());
+}
+
+fn fire_machine(
+    mut commands: Commands,
+    machine: Res<BallMachine>,
+    ball_assets: Res<BallAssets>,
+    timer: Res<MachineTimer>,
+    balls: Query<(), With<Ball>>,
+) {
+    // One ball at a time; the next one is fired after the previous shot is finished.
+    if !timer.0.just_finished() || !balls.is_empty() {
+        return;
+    }
+
+    let mut rng = rand::rng();
+    let range = machine.angle_range.to_radians();
+    let angle = if range > 0.0 {
+        PI + rng.random_range(-range..=range)
+    } else {
+        PI
+    };
+
+    fire_ball(
+        &mut commands,
+        &ball_assets,
+        MACHINE_POSITION - Vec2::new(MACHINE_SIZE.x, 0.0),
+        launch_velocity(angle, machine.speed),
+    );
+}
+
+fn on_shot_finished(
+    event: On<ShotFinished>,
+    mut commands: Commands,
+    machine: Res<BallMachine>,
+    balls: Query<&Position, With<Ball>>,
+    mut stats: ResMut<PracticeStats>,
+    mut timer: ResMut<MachineTimer>,
+) {
+    let Ok(position) = balls.get(event.ball) else {
+        return;
+    };
+
+    let result = if event.returned {
+        ShotResult::Returned {
+            offset: (position.y - MACHINE_POSITION.y).abs(),
+        }
+    } else {
+        ShotResult::Missed
+    };
+    info!("Practice shot finished: {:?}", result);
+    stats.0.push(result);
+
+    commands.entity(event.ball).despawn();
+    timer.0 = Timer::from_seconds(machine.interval, TimerMode::Once);
+}
@@ -0,0 +1,112 @@
+//! Slow-motion replay of the last few seconds before a goal.
+
+use crate::ball::Ball;
+use crate::paddle::Paddle;
+use crate::{AppState, AppSystems, MatchPhase, ReplayFinished, ScoreChanged};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Three seconds of history at the default 64 Hz fixed timestep.
+const REPLAY_SNAPSHOTS: usize = 3 * 64;
+const REPLAY_SPEED: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ReplayBuffer>();
+    app.init_resource::<ReplayCursor>();
+    app.add_observer(start_replay);
+
+    app.add_systems(OnEnter(AppState::Match), clear_buffer);
+    app.add_systems(
+        FixedUpdate,
+        record_snapshot.run_if(in_state(MatchPhase::Playing)),
+    );
+    app.add_systems(OnEnter(MatchPhase::Replay), (reset_cursor, pause_physics));
+    app.add_systems(OnExit(MatchPhase::Replay), unpause_physics);
+    app.add_systems(
+        Update,
+        play_replay
+            .run_if(in_state(MatchPhase::Replay))
+            .in_set(AppSystems::Update),
+    );
+}
+
+#[derive(Clone, Copy)]
+struct BodySnapshot {
+    entity: Entity,
+    position: Vec2,
+    velocity: Vec2,
+}
+
+/// Ring buffer of ball and paddle states, one entry per fixed step.
+#[derive(Resource, Default)]
+struct ReplayBuffer(VecDeque<Vec<BodySnapshot>>);
+
+/// Fractional index of the snapshot currently shown.
+#[derive(Resource, Default)]
+struct ReplayCursor(f32);
+
+fn start_replay(_event: On<ScoreChanged>, mut next_phase: ResMut<NextState<MatchPhase>>) {
+    next_phase.set(MatchPhase::Replay);
+}
+
+fn clear_buffer(mut buffer: ResMut<ReplayBuffer>) {
+    buffer.0.clear();
+}
+
+fn record_snapshot(
+    mut buffer: ResMut<ReplayBuffer>,
+    bodies: Query<(Entity, &Position, &LinearVelocity), Or<(With<Ball>, With<Paddle>)>>,
+) {
+    if buffer.0.len() == REPLAY_SNAPSHOTS {
+        buffer.0.pop_front();
+    }
+
+    let snapshot = bodies
+        .iter()
+        .map(|(entity, position, velocity)| BodySnapshot {
+            entity,
+            position: position.0,
+            velocity: velocity.0,
+        })
+        .collect();
+
+    buffer.0.push_back(snapshot);
+}
+
+fn reset_cursor(mut cursor: ResMut<ReplayCursor>) {
+    cursor.0 = 0.0;
+}
+
+fn pause_physics(mut physics_time: ResMut<Time<Physics>>) {
+    physics_time.pause();
+}
+
+fn unpause_physics(mut physics_time: ResMut<Time<Physics>>) {
+    physics_time.unpause();
+}
+
+fn play_replay(
+    mut commands: Commands,
+    time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
+    buffer: Res<ReplayBuffer>,
+    mut cursor: ResMut<ReplayCursor>,
+    mut bodies: Query<(&mut Position, &mut LinearVelocity, &mut Transform)>,
+) {
+    let Some(snapshot) = buffer.0.get(cursor.0 as usize) else {
+        commands.trigger(ReplayFinished);
+        return;
+    };
+
+    // Physics is paused, so write the transform as well as the position.
+    for body in snapshot {
+        if let Ok((mut position, mut velocity, mut transform)) = bodies.get_mut(body.entity) {
+            position.0 = body.position;
+            velocity.0 = body.velocity;
+            transform.translation = body.position.extend(transform.translation.z);
+        }
+    }
+
+    cursor.0 += time.delta_secs() * REPLAY_SPEED / fixed_time.timestep().as_secs_f32();
+}
@@ -0,0 +1,775 @@
+//! Local tournaments: a bracket of versus matches that is saved to disk after every result.
+
+use crate::{AppState, GameMode, MenuScreen, Score, ScoreChanged, WaitTimer, start_game};
+use bevy::input::ButtonState;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use bevy_enhanced_input::actions;
+use bevy_enhanced_input::prelude::*;
+
+/// Points needed to win a tournament match.
+pub const TOURNAMENT_POINTS: u32 = 5;
+const SAVE_PATH: &str = "pong_tournament.txt";
+const SAVE_HEADER: &str = "pong-tournament 1";
+const MAX_NAME_LENGTH: usize = 12;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<NameDraft>();
+    app.add_input_context::<TournamentControls>();
+    app.add_observer(apply_play_next_match);
+    app.add_observer(apply_create_elimination);
+    app.add_observer(apply_create_round_robin);
+    app.add_observer(apply_abandon_tournament);
+    app.add_observer(apply_leave_tournament_screen);
+    app.add_observer(record_match_result);
+
+    app.add_systems(Startup, load_tournament);
+    app.add_systems(OnEnter(MenuScreen::Tournament), spawn_tournament_controls);
+    app.add_systems(
+        Update,
+        enter_player_names
+            .run_if(in_state(MenuScreen::Tournament).and(not(resource_exists::<Tournament>))),
+    );
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TournamentKind {
+    SingleElimination,
+    RoundRobin,
+}
+
+/// Who plays on one side of a match.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Slot {
+    Player(usize),
+    /// Winner of the match with this index.
+    WinnerOf(usize),
+    /// No opponent, the other side advances without playing.
+    Bye,
+}
+
+#[derive(Debug, Clone)]
+pub struct TournamentMatch {
+    pub round: usize,
+    pub left: Slot,
+    pub right: Slot,
+    pub result: Option<(u32, u32)>,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct Tournament {
+    pub kind: TournamentKind,
+    pub players: Vec<String>,
+    pub matches: Vec<TournamentMatch>,
+    /// Match that is being played right now.
+    pub current: Option<usize>,
+}
+
+impl Tournament {
+    pub fn new(kind: TournamentKind, players: Vec<String>) -> Self {
+        let matches = match kind {
+            TournamentKind::SingleElimination => elimination_matches(players.len()),
+            TournamentKind::RoundRobin => round_robin_matches(players.len()),
+        };
+
+        Self {
+            kind,
+            players,
+            matches,
+            current: None,
+        }
+    }
+
+    pub fn player(&self, slot: Slot) -> Option<usize> {
+        match slot {
+            Slot::Player(player) => Some(player),
+            Slot::WinnerOf(index) => self.winner(index),
+            Slot::Bye => None,
+        }
+    }
+
+    pub fn winner(&self, index: usize) -> Option<usize> {
+        let tournament_match = &self.matches[index];
+        match (
+            tournament_match.left,
+            tournament_match.right,
+            tournament_match.result,
+        ) {
+            (left, _, Some((left_score, right_score))) if left_score > right_score => {
+                self.player(left)
+            }
+            (_, right, Some(_)) => self.player(right),
+            (Slot::Bye, right, None) => self.player(right),
+            (left, Slot::Bye, None) => self.player(left),
+            _ => None,
+        }
+    }
+
+    pub fn is_bye(&self, index: usize) -> bool {
+        let tournament_match = &self.matches[index];
+        tournament_match.left == Slot::Bye || tournament_match.right == Slot::Bye
+    }
+
+    /// First unplayed match whose both players are already known.
+    pub fn next_match(&self) -> Option<usize> {
+        (0..self.matches.len()).find(|&index| {
+            let tournament_match = &self.matches[index];
+            tournament_match.result.is_none()
+                && !self.is_bye(index)
+                && self.player(tournament_match.left).is_some()
+                && self.player(tournament_match.right).is_some()
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        (0..self.matches.len())
+            .all(|index| self.matches[index].result.is_some() || self.is_bye(index))
+    }
+
+    pub fn champion(&self) -> Option<usize> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        match self.kind {
+            TournamentKind::SingleElimination => self.winner(self.matches.len().checked_sub(1)?),
+            TournamentKind::RoundRobin => self.standings().first().map(|standing| standing.player),
+        }
+    }
+
+    /// Players ordered by wins, then by point difference.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (0..self.players.len())
+            .map(|player| Standing {
+                player,
+                wins: 0,
+                point_difference: 0,
+            })
+            .collect();
+
+        for (index, tournament_match) in self.matches.iter().enumerate() {
+            let Some((left_score, right_score)) = tournament_match.result else {
+                continue;
+            };
+            let (Some(left), Some(right)) = (
+                self.player(tournament_match.left),
+                self.player(tournament_match.right),
+            ) else {
+                continue;
+            };
+
+            let difference = left_score as i32 - right_score as i32;
+            standings[left].point_difference += difference;
+            standings[right].point_difference -= difference;
+            if let Some(winner) = self.winner(index) {
+                standings[winner].wins += 1;
+            }
+        }
+
+        standings.sort_by(|a, b| {
+            b.wins
+                .cmp(&a.wins)
+                .then(b.point_difference.cmp(&a.point_difference))
+        });
+        standings
+    }
+
+    fn to_save_string(&self) -> String {
+        let mut lines = vec![SAVE_HEADER.to_string()];
+        lines.push(match self.kind {
+            TournamentKind::SingleElimination => "kind single-elimination".to_string(),
+            TournamentKind::RoundRobin => "kind round-robin".to_string(),
+        });
+        for player in &self.players {
+            lines.push(format!("player {player}"));
+        }
+        for tournament_match in &self.matches {
+            let result = match tournament_match.result {
+                Some((left, right)) => format!("{left}:{right}"),
+                None => "-".to_string(),
+            };
+            lines.push(format!(
+                "match {} {} {} {}",
+                tournament_match.round,
+                slot_to_string(tournament_match.left),
+                slot_to_string(tournament_match.right),
+                result
+            ));
+        }
+        lines.join("\n")
+    }
+
+    fn from_save_string(save: &str) -> Result<Self, String> {
+        let mut lines = save.lines().enumerate();
+        match lines.next() {
+            Some((_, SAVE_HEADER)) => (),
+            _ => return Err("not a tournament save or an unsupported version".to_string()),
+        }
+
+        let mut kind = None;
+        let mut players = Vec::new();
+        let mut matches = Vec::new();
+
+        for (number, line) in lines {
+            let error = |message: &str| format!("line {}: {message}", number + 1);
+            let (key, value) = line.split_once(' ').ok_or_else(|| error("missing value"))?;
+            match key {
+                "kind" => {
+                    kind = Some(match value {
+                        "single-elimination" => TournamentKind::SingleElimination,
+                        "round-robin" => TournamentKind::RoundRobin,
+                        _ => return Err(error("unknown tournament kind")),
+                    });
+                }
+                "player" => players.push(value.to_string()),
+                "match" => {
+                    let parts: Vec<&str> = value.split(' ').collect();
+                    let [round, left, right, result] = parts[..] else {
+                        return Err(error("expected round, two slots and a result"));
+                    };
+                    let result = match result {
+                        "-" => None,
+                        result => {
+                            let (left, right) =
+                                result.split_once(':').ok_or_else(|| error("bad result"))?;
+                            Some((
+                                left.parse().map_err(|_| error("bad result"))?,
+                                right.parse().map_err(|_| error("bad result"))?,
+                            ))
+                        }
+                    };
+                    matches.push(TournamentMatch {
+                        round: round.parse().map_err(|_| error("bad round"))?,
+                        left: slot_from_str(left).ok_or_else(|| error("bad slot"))?,
+                        right: slot_from_str(right).ok_or_else(|| error("bad slot"))?,
+                        result,
+                    });
+                }
+                _ => return Err(error("unknown entry")),
+            }
+        }
+
+        let tournament = Self {
+            kind: kind.ok_or("missing tournament kind")?,
+            players,
+            matches,
+            current: None,
+        };
+
+        // Reject references that would make the bracket lookups panic.
+        let valid_slot = |slot: Slot, index: usize| match slot {
+            Slot::Player(player) => player < tournament.players.len(),
+            Slot::WinnerOf(other) => other < index,
+            Slot::Bye => true,
+        };
+        let all_valid = tournament
+            .matches
+            .iter()
+            .enumerate()
+            .all(|(index, m)| valid_slot(m.left, index) && valid_slot(m.right, index));
+        if !all_valid {
+            return Err("match refers to an unknown player or match".to_string());
+        }
+
+        Ok(tournament)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Standing {
+    pub player: usize,
+    pub wins: u32,
+    pub point_difference: i32,
+}
+
+/// Names typed on the tournament screen before the tournament is created.
+#[derive(Resource, Default, Debug)]
+pub struct NameDraft {
+    pub names: Vec<String>,
+    pub current: String,
+}
+
+#[derive(Component)]
+struct TournamentControls;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct PlayNextMatchAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct CreateEliminationAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct CreateRoundRobinAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct AbandonTournamentAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct LeaveTournamentScreenAction;
+
+/// Standard bracket order (1v8, 4v5, 2v7, 3v6, …), so the top two seeds can
+/// only meet in the final. Missing players become byes for the top seeds.
+fn elimination_matches(player_count: usize) -> Vec<TournamentMatch> {
+    let size = player_count.next_power_of_two();
+    let seed = |index: usize| {
+        if index < player_count {
+            Slot::Player(index)
+        } else {
+            Slot::Bye
+        }
+    };
+
+    // Each doubling pairs every seed with the one that mirrors it in the bigger bracket.
+    let mut order = vec![0];
+    while order.len() < size {
+        let length = order.len() * 2;
+        order = order
+            .into_iter()
+            .flat_map(|index| [index, length - 1 - index])
+            .collect();
+    }
+
+    let mut matches: Vec<TournamentMatch> = order
+        .chunks(2)
+        .map(|pair| TournamentMatch {
+            round: 0,
+            left: seed(pair[0]),
+            right: seed(pair[1]),
+            result: None,
+        })
+        .collect();
+
+    let mut round_start = 0;
+    let mut round_size = size / 2;
+    let mut round = 1;
+    while round_size > 1 {
+        for index in (0..round_size).step_by(2) {
+            matches.push(TournamentMatch {
+                round,
+                left: Slot::WinnerOf(round_start + index),
+                right: Slot::WinnerOf(round_start + index + 1),
+                result: None,
+            });
+        }
+        round_start += round_size;
+        round_size /= 2;
+        round += 1;
+    }
+
+    matches
+}
+
+/// Circle method: one player stays in place while the others rotate around them.
+fn round_robin_matches(player_count: usize) -> Vec<TournamentMatch> {
+    let mut seats: Vec<Slot> = (0..player_count).map(Slot::Player).collect();
+    if seats.len() % 2 == 1 {
+        seats.push(Slot::Bye);
+    }
+
+    let mut matches = Vec::new();
+    for round in 0..seats.len().saturating_sub(1) {
+        for index in 0..seats.len() / 2 {
+            let (left, right) = (seats[index], seats[seats.len() - 1 - index]);
+            if left != Slot::Bye && right != Slot::Bye {
+                matches.push(TournamentMatch {
+                    round,
+                    left,
+                    right,
+                    result: None,
+                });
+            }
+        }
+        seats[1..].rotate_right(1);
+    }
+
+    matches
+}
+
+fn slot_to_string(slot: Slot) -> String {
+    match slot {
+        Slot::Player(player) => format!("p{player}"),
+        Slot::WinnerOf(index) => format!("w{index}"),
+        Slot::Bye => "bye".to_string(),
+    }
+}
+
+fn slot_from_str(value: &str) -> Option<Slot> {
+    if value == "bye" {
+        return Some(Slot::Bye);
+    }
+    if let Some(player) = value.strip_prefix('p') {
+        return player.parse().ok().map(Slot::Player);
+    }
+    value.strip_prefix('w')?.parse().ok().map(Slot::WinnerOf)
+}
+
+fn save_tournament(tournament: &Tournament) {
+    if let Err(error) = std::fs::write(SAVE_PATH, tournament.to_save_string()) {
+        warn!("Failed to save the tournament: {error}");
+    }
+}
+
+fn load_tournament(mut commands: Commands) {
+    let Ok(save) = std::fs::read_to_string(SAVE_PATH) else {
+        return;
+    };
+
+    match Tournament::from_save_string(&save) {
+        Ok(tournament) => {
+            info!("Resuming a saved tournament");
+            commands.insert_resource(tournament);
+        }
+        Err(error) => warn!("Ignoring the saved tournament: {error}"),
+    }
+}
+
+fn spawn_tournament_controls(mut commands: Commands) {
+    commands.spawn((
+        TournamentControls,
+        DespawnOnExit(MenuScreen::Tournament),
+        actions!(
+            TournamentControls[
+                (Action::<PlayNextMatchAction>::new(), bindings![KeyCode::Enter]),
+                (Action::<CreateEliminationAction>::new(), bindings![KeyCode::F1]),
+                (Action::<CreateRoundRobinAction>::new(), bindings![KeyCode::F2]),
+                (Action::<AbandonTournamentAction>::new(), bindings![KeyCode::Delete]),
+                (Action::<LeaveTournamentScreenAction>::new(), bindings![KeyCode::Escape]),
+            ]
+        ),
+    ));
+}
+
+/// Key presses from the frame the screen opened are skipped, so the menu key isn't typed into a name.
+fn enter_player_names(
+    mut keyboard: MessageReader<KeyboardInput>,
+    screen: Res<State<MenuScreen>>,
+    mut draft: ResMut<NameDraft>,
+) {
+    if screen.is_changed() {
+        keyboard.clear();
+        return;
+    }
+
+    for input in keyboard.read() {
+        if input.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match input.key_code {
+            KeyCode::Enter => {
+                let name = draft.current.trim().to_string();
+                if !name.is_empty() {
+                    draft.names.push(name);
+                }
+                draft.current.clear();
+            }
+            KeyCode::Backspace => {
+                if draft.current.pop().is_none() {
+                    draft.names.pop();
+                }
+            }
+            _ => {
+                let Some(text) = &input.text else {
+                    continue;
+                };
+                for character in text.chars() {
+                    if (character.is_alphanumeric() || character == ' ')
+                        && draft.current.chars().count() < MAX_NAME_LENGTH
+                    {
+                        draft.current.push(character);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn apply_create_elimination(
+    _event: On<Start<CreateEliminationAction>>,
+    commands: Commands,
+    draft: ResMut<NameDraft>,
+    tournament: Option<Res<Tournament>>,
+) {
+    create_tournament(
+        TournamentKind::SingleElimination,
+        commands,
+        draft,
+        tournament,
+    );
+}
+
+fn apply_create_round_robin(
+    _event: On<Start<CreateRoundRobinAction>>,
+    commands: Commands,
+    draft: ResMut<NameDraft>,
+    tournament: Option<Res<Tournament>>,
+) {
+    create_tournament(TournamentKind::RoundRobin, commands, draft, tournament);
+}
+
+fn create_tournament(
+    kind: TournamentKind,
+    mut commands: Commands,
+    mut draft: ResMut<NameDraft>,
+    tournament: Option<Res<Tournament>>,
+) {
+    if tournament.is_some() {
+        return;
+    }
+
+    let mut names = std::mem::take(&mut draft.names);
+    let current = draft.current.trim().to_string();
+    if !current.is_empty() {
+        names.push(current);
+    }
+    draft.current.clear();
+
+    if names.len() < 2 {
+        draft.names = names;
+        return;
+    }
+
+    let tournament = Tournament::new(kind, names);
+    save_tournament(&tournament);
+    commands.insert_resource(tournament);
+}
+
+fn apply_play_next_match(
+    _event: On<Start<PlayNextMatchAction>>,
+    tournament: Option<ResMut<Tournament>>,
+    game_mode: ResMut<GameMode>,
+    timer: ResMut<WaitTimer>,
+    next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(mut tournament) = tournament else {
+        return;
+    };
+    let Some(index) = tournament.next_match() else {
+        return;
+    };
+
+    tournament.current = Some(index);
+    start_game(GameMode::Tournament, game_mode, timer, next_state);
+}
+
+fn apply_abandon_tournament(
+    _event: On<Start<AbandonTournamentAction>>,
+    mut commands: Commands,
+    tournament: Option<Res<Tournament>>,
+) {
+    if tournament.is_none() {
+        return;
+    }
+
+    commands.remove_resource::<Tournament>();
+    if let Err(error) = std::fs::remove_file(SAVE_PATH) {
+        warn!("Failed to remove the tournament save: {error}");
+    }
+}
+
+fn apply_leave_tournament_screen(
+    _event: On<Start<LeaveTournamentScreenAction>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    next_screen.set(MenuScreen::Main);
+}
+
+/// Ends the match once a player reaches [`TOURNAMENT_POINTS`] and goes back to the bracket.
+fn record_match_result(
+    _event: On<ScoreChanged>,
+    game_mode: Res<GameMode>,
+    score: Res<Score>,
+    tournament: Option<ResMut<Tournament>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut next_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if *game_mode != GameMode::Tournament || score.left.max(score.right) < TOURNAMENT_POINTS {
+        return;
+    }
+    let Some(mut tournament) = tournament else {
+        return;
+    };
+    let Some(index) = tournament.current.take() else {
+        return;
+    };
+
+    tournament.matches[index].result = Some((score.left, score.right));
+    save_tournament(&tournament);
+
+    next_state.set(AppState::Menu);
+    next_screen.set(MenuScreen::Tournament);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|player| format!("Player {player}"))
+            .collect()
+    }
+
+    fn pairing(tournament_match: &TournamentMatch) -> (Slot, Slot) {
+        (tournament_match.left, tournament_match.right)
+    }
+
+    /// Plays the next match, the left side winning when `left_wins` is set.
+    fn play(tournament: &mut Tournament, left_wins: bool) -> usize {
+        let index = tournament.next_match().unwrap();
+        tournament.matches[index].result = Some(if left_wins { (5, 3) } else { (1, 5) });
+        index
+    }
+
+    #[test]
+    fn elimination_gives_the_top_seeds_byes() {
+        let tournament = Tournament::new(TournamentKind::SingleElimination, players(5));
+
+        let pairings: Vec<_> = tournament.matches.iter().map(pairing).collect();
+        assert_eq!(
+            pairings,
+            vec![
+                (Slot::Player(0), Slot::Bye),
+                (Slot::Player(3), Slot::Player(4)),
+                (Slot::Player(1), Slot::Bye),
+                (Slot::Player(2), Slot::Bye),
+                (Slot::WinnerOf(0), Slot::WinnerOf(1)),
+                (Slot::WinnerOf(2), Slot::WinnerOf(3)),
+                (Slot::WinnerOf(4), Slot::WinnerOf(5)),
+            ]
+        );
+        assert_eq!(tournament.matches[6].round, 2);
+        assert_eq!(tournament.winner(0), Some(0));
+        assert_eq!(tournament.next_match(), Some(1));
+    }
+
+    /// Players who can come out of `slot`.
+    fn seeds(tournament: &Tournament, slot: Slot) -> Vec<usize> {
+        match slot {
+            Slot::Player(player) => vec![player],
+            Slot::WinnerOf(index) => {
+                let tournament_match = &tournament.matches[index];
+                let mut players = seeds(tournament, tournament_match.left);
+                players.extend(seeds(tournament, tournament_match.right));
+                players
+            }
+            Slot::Bye => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn top_two_seeds_only_meet_in_the_final() {
+        for count in 2..=16 {
+            let tournament = Tournament::new(TournamentKind::SingleElimination, players(count));
+            let final_match = tournament.matches.last().unwrap();
+
+            assert_eq!(seeds(&tournament, final_match.left)[0], 0);
+            assert!(seeds(&tournament, final_match.right).contains(&1));
+        }
+    }
+
+    #[test]
+    fn elimination_winners_advance_to_the_final() {
+        let mut tournament = Tournament::new(TournamentKind::SingleElimination, players(4));
+
+        assert_eq!(play(&mut tournament, true), 0);
+        assert_eq!(play(&mut tournament, false), 1);
+        assert_eq!(tournament.champion(), None);
+
+        // Player 0 against player 2, who beat the second seed.
+        let final_match = play(&mut tournament, false);
+        assert_eq!(final_match, 2);
+        assert_eq!(tournament.winner(final_match), Some(2));
+        assert!(tournament.is_finished());
+        assert_eq!(tournament.next_match(), None);
+        assert_eq!(tournament.champion(), Some(2));
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once() {
+        for count in 2..=7 {
+            let tournament = Tournament::new(TournamentKind::RoundRobin, players(count));
+
+            let mut pairs: Vec<_> = tournament
+                .matches
+                .iter()
+                .map(|tournament_match| {
+                    let left = tournament.player(tournament_match.left).unwrap();
+                    let right = tournament.player(tournament_match.right).unwrap();
+                    (left.min(right), left.max(right))
+                })
+                .collect();
+            pairs.sort();
+            pairs.dedup();
+            assert_eq!(pairs.len(), count * (count - 1) / 2);
+            assert_eq!(tournament.matches.len(), pairs.len());
+        }
+    }
+
+    #[test]
+    fn round_robin_rounds_have_each_player_at_most_once() {
+        let tournament = Tournament::new(TournamentKind::RoundRobin, players(5));
+
+        for round in 0..5 {
+            let mut seen = Vec::new();
+            for tournament_match in tournament.matches.iter().filter(|m| m.round == round) {
+                seen.extend([tournament_match.left, tournament_match.right]);
+            }
+            let count = seen.len();
+            seen.sort_by_key(|slot| tournament.player(*slot));
+            seen.dedup();
+            assert_eq!(seen.len(), count);
+        }
+    }
+
+    #[test]
+    fn standings_rank_by_wins_then_point_difference() {
+        let mut tournament = Tournament::new(TournamentKind::RoundRobin, players(3));
+        for (tournament_match, result) in
+            tournament.matches.iter_mut().zip([(5, 0), (4, 5), (5, 4)])
+        {
+            tournament_match.result = Some(result);
+        }
+
+        // Every player wins once, so the point difference decides.
+        let standings = tournament.standings();
+        assert!(standings.iter().all(|standing| standing.wins == 1));
+        let differences: Vec<_> = standings
+            .iter()
+            .map(|standing| standing.point_difference)
+            .collect();
+        assert_eq!(differences, vec![4, 0, -4]);
+        assert_eq!(tournament.champion(), Some(standings[0].player));
+    }
+
+    #[test]
+    fn saves_survive_a_round_trip() {
+        let mut tournament = Tournament::new(TournamentKind::SingleElimination, players(3));
+        play(&mut tournament, false);
+
+        let save = tournament.to_save_string();
+        let loaded = Tournament::from_save_string(&save).unwrap();
+        assert_eq!(loaded.kind, TournamentKind::SingleElimination);
+        assert_eq!(loaded.players, tournament.players);
+        assert_eq!(loaded.to_save_string(), save);
+        assert_eq!(loaded.next_match(), tournament.next_match());
+    }
+
+    #[test]
+    fn rejects_out_of_range_slots() {
+        let save = |matches: &str| {
+            format!("{SAVE_HEADER}\nkind round-robin\nplayer Ann\nplayer Bob\n{matches}")
+        };
+
+        assert!(Tournament::from_save_string(&save("match 0 p0 p1 -")).is_ok());
+        assert!(Tournament::from_save_string(&save("match 0 p0 p2 -")).is_err());
+        assert!(Tournament::from_save_string(&save("match 0 p0 w0 -")).is_err());
+        assert!(Tournament::from_save_string(&save("match 0 p0 p1 -\nmatch 1 w0 w1 -")).is_err());
+        assert!(Tournament::from_save_string("pong-tournament 2\nkind round-robin").is_err());
+    }
+}
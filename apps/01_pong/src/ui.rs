@@ -1,19 +1,46 @@
 use crate::asset_tracking::{ResourceHandles, ResourceLoadState};
+use crate::paddle::{PaddleConfig, PaddleSettings, PaddleSide};
+use crate::practice::{BallMachine, PracticeStats, ShotResult};
 use crate::scene::GROUND_HEIGHT;
-use crate::{AppState, AssetState, Score, ScoreChanged};
+use crate::tournament::{NameDraft, Slot, TOURNAMENT_POINTS, Tournament, TournamentKind};
+use crate::{AppState, AssetState, GameMode, MatchPhase, MenuScreen, Score, ScoreChanged};
 use bevy::color::palettes::css::RED;
 use bevy::prelude::*;
 use bevy::text::LineHeight;
 
 pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(MenuScreen::Main), spawn_menu_ui);
+    app.add_systems(OnEnter(MenuScreen::Tournament), spawn_tournament_ui);
+    app.add_systems(
+        Update,
+        update_tournament_ui.run_if(in_state(MenuScreen::Tournament)),
+    );
+    app.add_systems(
+        Update,
+        (
+            update_menu_ui.run_if(resource_changed::<BallMachine>),
+            update_paddle_settings_ui.run_if(resource_changed::<PaddleConfig>),
+        )
+            .run_if(in_state(AppState::Menu)),
+    );
     app.add_systems(
         OnTransition {
             exited: AppState::Menu,
             entered: AppState::Waiting,
         },
-        (spawn_score_ui, spawn_restart_ui),
+        (
+            spawn_score_ui.run_if(not(resource_equals(GameMode::Practice))),
+            spawn_practice_ui.run_if(resource_equals(GameMode::Practice)),
+            spawn_tournament_match_ui.run_if(resource_equals(GameMode::Tournament)),
+            spawn_restart_ui,
+        ),
+    );
+    app.add_systems(
+        Update,
+        update_practice_ui.run_if(resource_changed::<PracticeStats>),
     );
 
+    app.add_systems(OnEnter(MatchPhase::Replay), spawn_replay_ui);
     app.add_systems(OnEnter(AssetState::Error), spawn_asset_errors);
     app.add_observer(update_score_ui);
 }
@@ -21,7 +48,238 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Component)]
 struct ScoreText;
 
-fn spawn_score_ui(mut commands: Commands) {
+#[derive(Component)]
+struct MachineSettingsText;
+
+#[derive(Component)]
+struct PracticeStatsText;
+
+#[derive(Component)]
+struct PaddleSettingsText;
+
+#[derive(Component)]
+struct TournamentText;
+
+fn spawn_menu_ui(
+    mut commands: Commands,
+    machine: Res<BallMachine>,
+    paddle_config: Res<PaddleConfig>,
+    tournament: Option<Res<Tournament>>,
+) {
+    let tournament_text = if tournament.is_some() {
+        "Press '3' to continue the tournament"
+    } else {
+        "Press '3' to set up a tournament"
+    };
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: vw(100),
+            height: vh(100),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: px(10),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        DespawnOnExit(MenuScreen::Main),
+        children![
+            (Text::new("Pong"), TextFont::from_font_size(64.0)),
+            Text::new("Press '1' to play versus"),
+            Text::new("Press '2' to practice against the ball machine"),
+            Text::new(tournament_text),
+            (
+                MachineSettingsText,
+                Text::new(machine_settings_text(&machine)),
+                TextLayout::new_with_justify(Justify::Center),
+                TextFont::from_font_size(20.0),
+            ),
+            (
+                PaddleSettingsText,
+                Text::new(paddle_settings_text(&paddle_config)),
+                TextLayout::new_with_justify(Justify::Center),
+                TextFont::from_font_size(20.0),
+            ),
+        ],
+    ));
+}
+
+fn update_menu_ui(
+    machine: Res<BallMachine>,
+    mut text: Single<&mut Text, With<MachineSettingsText>>,
+) {
+    text.0 = machine_settings_text(&machine);
+}
+
+fn update_paddle_settings_ui(
+    paddle_config: Res<PaddleConfig>,
+    mut text: Single<&mut Text, With<PaddleSettingsText>>,
+) {
+    text.0 = paddle_settings_text(&paddle_config);
+}
+
+fn paddle_settings_text(config: &PaddleConfig) -> String {
+    let side = |name: &str, side: PaddleSide, settings: &PaddleSettings| {
+        let marker = if config.selected == side { ">" } else { " " };
+        format!(
+            "{marker} {name}: length {:.0}, speed {:.0}, responsiveness {:.0}, starting score {}",
+            settings.length, settings.speed, settings.responsiveness, settings.starting_score
+        )
+    };
+
+    format!(
+        "Paddles [Tab] switch side, [T/G] length, [Y/H] speed, [U/J] responsiveness, [I/K] starting score\n{}\n{}",
+        side("Left", PaddleSide::Left, &config.left),
+        side("Right", PaddleSide::Right, &config.right),
+    )
+}
+
+fn machine_settings_text(machine: &BallMachine) -> String {
+    format!(
+        "Ball machine\n\
+         [Q/A] shot speed: {:.0}\n\
+         [W/S] angle range: {:.0} deg\n\
+         [E/D] delay between shots: {:.2} s",
+        machine.speed, machine.angle_range, machine.interval
+    )
+}
+
+fn spawn_tournament_ui(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: vw(100),
+            height: vh(100),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        DespawnOnExit(MenuScreen::Tournament),
+        children![(
+            TournamentText,
+            Text::default(),
+            TextFont::from_font_size(20.0),
+        )],
+    ));
+}
+
+fn update_tournament_ui(
+    tournament: Option<Res<Tournament>>,
+    draft: Res<NameDraft>,
+    mut text: Single<&mut Text, With<TournamentText>>,
+) {
+    let content = match tournament {
+        Some(tournament) => bracket_text(&tournament),
+        None => name_entry_text(&draft),
+    };
+    text.set_if_neq(Text::new(content));
+}
+
+fn name_entry_text(draft: &NameDraft) -> String {
+    let mut lines = vec!["New tournament - type a name and press 'enter' to add it".to_string()];
+    for (index, name) in draft.names.iter().enumerate() {
+        lines.push(format!("{}. {name}", index + 1));
+    }
+    lines.push(format!("> {}_", draft.current));
+    lines.push(String::new());
+    lines.push("[F1] single elimination  [F2] round robin  [Esc] back".to_string());
+    lines.join("\n")
+}
+
+fn bracket_text(tournament: &Tournament) -> String {
+    let name = |slot: Slot| match (slot, tournament.player(slot)) {
+        (_, Some(player)) => tournament.players[player].clone(),
+        (Slot::Bye, None) => "(bye)".to_string(),
+        (_, None) => "?".to_string(),
+    };
+
+    let mut lines = vec![match tournament.kind {
+        TournamentKind::SingleElimination => "Single elimination".to_string(),
+        TournamentKind::RoundRobin => "Round robin".to_string(),
+    }];
+
+    let mut round = None;
+    for (index, tournament_match) in tournament.matches.iter().enumerate() {
+        if tournament.is_bye(index) {
+            continue;
+        }
+        if round != Some(tournament_match.round) {
+            round = Some(tournament_match.round);
+            lines.push(format!("Round {}", tournament_match.round + 1));
+        }
+
+        let result = match tournament_match.result {
+            Some((left, right)) => format!("{left} : {right}"),
+            None if tournament.next_match() == Some(index) => "next".to_string(),
+            None => "-".to_string(),
+        };
+        lines.push(format!(
+            "    {} vs {}    {result}",
+            name(tournament_match.left),
+            name(tournament_match.right)
+        ));
+    }
+
+    if tournament.kind == TournamentKind::RoundRobin {
+        lines.push("Standings".to_string());
+        for standing in tournament.standings() {
+            lines.push(format!(
+                "    {}: {} wins, {:+} points",
+                tournament.players[standing.player], standing.wins, standing.point_difference
+            ));
+        }
+    }
+
+    lines.push(String::new());
+    match tournament.champion() {
+        Some(champion) => lines.push(format!(
+            "{} wins the tournament!",
+            tournament.players[champion]
+        )),
+        None => lines.push(format!(
+            "[Enter] play the next match (first to {TOURNAMENT_POINTS})"
+        )),
+    }
+    lines.push("[Delete] abandon the tournament  [Esc] back".to_string());
+    lines.join("\n")
+}
+
+fn spawn_tournament_match_ui(mut commands: Commands, tournament: Option<Res<Tournament>>) {
+    let Some(tournament) = tournament else {
+        return;
+    };
+    let Some(index) = tournament.current else {
+        return;
+    };
+
+    let tournament_match = &tournament.matches[index];
+    let player_name = |slot: Slot| {
+        tournament
+            .player(slot)
+            .map(|player| tournament.players[player].clone())
+            .unwrap_or_default()
+    };
+
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(GROUND_HEIGHT),
+            width: percent(100),
+            ..default()
+        },
+        Text::new(format!(
+            "{} vs {} - first to {TOURNAMENT_POINTS}",
+            player_name(tournament_match.left),
+            player_name(tournament_match.right)
+        )),
+        TextLayout::new_with_justify(Justify::Center),
+        TextFont::from_font_size(20.0),
+        DespawnOnEnter(AppState::Menu),
+    ));
+}
+
+fn spawn_score_ui(mut commands: Commands, score: Res<Score>) {
     commands.spawn((
         ScoreText,
         Node {
@@ -31,7 +289,7 @@ fn spawn_score_ui(mut commands: Commands) {
             height: px(GROUND_HEIGHT),
             ..default()
         },
-        Text::new("0 : 0"),
+        Text::new(format!("{} : {}", score.left, score.right)),
         TextLayout::new_with_justify(Justify::Center),
         TextFont::from_font_size(42.0),
         LineHeight::Px(GROUND_HEIGHT),
@@ -39,6 +297,54 @@ fn spawn_score_ui(mut commands: Commands) {
     ));
 }
 
+fn spawn_practice_ui(mut commands: Commands, stats: Res<PracticeStats>) {
+    commands.spawn((
+        PracticeStatsText,
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(0),
+            width: percent(100),
+            height: px(GROUND_HEIGHT),
+            ..default()
+        },
+        Text::new(practice_stats_text(&stats)),
+        TextLayout::new_with_justify(Justify::Center),
+        TextFont::from_font_size(28.0),
+        LineHeight::Px(GROUND_HEIGHT),
+        DespawnOnEnter(AppState::Menu),
+    ));
+}
+
+fn update_practice_ui(
+    stats: Res<PracticeStats>,
+    mut text: Single<&mut Text, With<PracticeStatsText>>,
+) {
+    text.0 = practice_stats_text(&stats);
+}
+
+fn practice_stats_text(stats: &PracticeStats) -> String {
+    let shots = stats.0.len();
+    let returned = stats.returned();
+    let accuracy = if shots > 0 {
+        returned as f32 / shots as f32 * 100.0
+    } else {
+        0.0
+    };
+
+    let last = match stats.0.last() {
+        Some(ShotResult::Returned { offset }) => format!("last: returned {offset:.0}px off"),
+        Some(ShotResult::Missed) => "last: missed".to_string(),
+        None => "waiting for the first shot".to_string(),
+    };
+
+    match stats.average_offset() {
+        Some(offset) => {
+            format!("Returned {returned}/{shots} ({accuracy:.0}%), avg {offset:.0}px off, {last}")
+        }
+        None => format!("Returned {returned}/{shots} ({accuracy:.0}%), {last}"),
+    }
+}
+
 fn spawn_restart_ui(mut commands: Commands) {
     commands.spawn((
         Node {
@@ -48,7 +354,7 @@ fn spawn_restart_ui(mut commands: Commands) {
             height: px(GROUND_HEIGHT),
             ..default()
         },
-        Text::new("Press 'r' to restart"),
+        Text::new("Press 'r' to go back to the menu"),
         TextLayout::new_with_justify(Justify::Center),
         TextFont::from_font_size(20.0),
         LineHeight::Px(GROUND_HEIGHT),
@@ -56,6 +362,27 @@ fn spawn_restart_ui(mut commands: Commands) {
     ));
 }
 
+fn spawn_replay_ui(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(GROUND_HEIGHT),
+            width: percent(100),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        DespawnOnExit(MatchPhase::Replay),
+        children![
+            (Text::new("REPLAY"), TextFont::from_font_size(42.0)),
+            (
+                Text::new("Press 'space' to skip"),
+                TextFont::from_font_size(20.0)
+            ),
+        ],
+    ));
+}
+
 fn update_score_ui(
     _event: On<ScoreChanged>,
     score: Res<Score>,
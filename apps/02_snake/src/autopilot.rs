@@ -0,0 +1,467 @@
+//! A bot that drives the snake: for the autopilot setting, the attract-mode demo
+//! shown when the menu is left idle, and the soak test below.
+//!
+//! The bot takes the shortest path to the food, but only if the snake could
+//! still reach its own tail afterwards. Otherwise it chases its tail, which
+//! keeps it alive until the food becomes safe to take.
+
+use crate::board::{Direction, SnakeBoard};
+use crate::food::{Food, FoodKind};
+use crate::grid::GridPos;
+use crate::player::{Board, move_snake};
+use crate::replay::Playback;
+use crate::{AppState, AppSystems, PausableSystems};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// Menu idle time before the demo starts.
+const ATTRACT_DELAY: f32 = 10.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Autopilot>();
+    app.init_resource::<AttractMode>();
+    app.init_resource::<IdleTimer>();
+
+    app.add_systems(
+        OnEnter(AppState::Menu),
+        (stop_attract_mode, reset_idle_timer),
+    );
+    app.add_systems(
+        Update,
+        tick_idle_timer
+            .run_if(in_state(AppState::Menu))
+            .in_set(AppSystems::TickTimers),
+    );
+    app.add_systems(
+        Update,
+        start_attract_mode
+            .run_if(in_state(AppState::Menu))
+            .in_set(AppSystems::Update),
+    );
+    app.add_systems(
+        Update,
+        drive_snake
+            .after(move_snake)
+            .run_if(in_state(AppState::Playing).and(bot_is_driving))
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        leave_attract_mode
+            .run_if(in_state(AppState::Playing).and(resource_equals(AttractMode(true)))),
+    );
+    app.add_systems(
+        OnEnter(AppState::GameOver),
+        back_to_menu.run_if(resource_equals(AttractMode(true))),
+    );
+    app.add_systems(
+        OnEnter(AppState::Won),
+        back_to_menu.run_if(resource_equals(AttractMode(true))),
+    );
+}
+
+/// Lets the bot play instead of the player, toggled in the menu. Arrow keys
+/// still steer, the bot carries on from the new heading. In versus games the
+/// bot plays the second snake.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Autopilot(pub bool);
+
+/// Set while the bot plays a demo game started from an idle menu.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttractMode(pub bool);
+
+#[derive(Resource)]
+struct IdleTimer(Timer);
+
+impl Default for IdleTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(ATTRACT_DELAY, TimerMode::Once))
+    }
+}
+
+/// Never while watching a replay, which already knows every move.
+fn bot_is_driving(
+    autopilot: Res<Autopilot>,
+    attract_mode: Res<AttractMode>,
+    playback: Option<Res<Playback>>,
+) -> bool {
+    (autopilot.0 || attract_mode.0) && playback.is_none()
+}
+
+/// What the bot knows about the board while planning for one snake.
+struct Planner<'a> {
+    board: &'a SnakeBoard,
+    /// Cells of the other snakes and of poison food, row-major from the bottom
+    /// row. They are treated like walls: the bot doesn't guess where the other
+    /// player goes, and never eats poison.
+    obstacles: Vec<bool>,
+}
+
+impl<'a> Planner<'a> {
+    fn new(board: &'a SnakeBoard, player: usize, poison: bool) -> Self {
+        let size = board.size();
+        let mut obstacles = vec![false; (size.width * size.height) as usize];
+        for (other, snake) in board.snakes().iter().enumerate() {
+            if other != player {
+                for &pos in snake.body() {
+                    obstacles[(pos.y * size.width + pos.x) as usize] = true;
+                }
+            }
+        }
+        if poison && let Some(food) = board.food() {
+            obstacles[(food.y * size.width + food.x) as usize] = true;
+        }
+
+        Self { board, obstacles }
+    }
+
+    fn index(&self, pos: GridPos) -> usize {
+        (pos.y * self.board.size().width + pos.x) as usize
+    }
+
+    /// The cell one step away, or `None` if that step hits a wall, an obstacle,
+    /// the other snake or poison, or leaves a solid board.
+    fn neighbor(&self, pos: GridPos, direction: Direction) -> Option<GridPos> {
+        self.board.neighbor(pos, direction).filter(|&next| {
+            self.board.cell(next).is_passable() && !self.obstacles[self.index(next)]
+        })
+    }
+
+    /// Breadth-first search from `from` to `to`, avoiding `snake` except for its
+    /// tail, which moves out of the way. Returns the path without `from`.
+    fn path(&self, snake: &VecDeque<GridPos>, from: GridPos, to: GridPos) -> Option<Vec<GridPos>> {
+        let mut blocked = vec![false; self.obstacles.len()];
+        for &pos in snake.iter().take(snake.len().saturating_sub(1)) {
+            blocked[self.index(pos)] = true;
+        }
+
+        let mut came_from: Vec<Option<GridPos>> = vec![None; self.obstacles.len()];
+        let mut queue = VecDeque::from([from]);
+        blocked[self.index(from)] = true;
+
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                let mut path = vec![pos];
+                let mut current = pos;
+                while let Some(previous) = came_from[self.index(current)]
+                    && previous != from
+                {
+                    path.push(previous);
+                    current = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for direction in Direction::ALL {
+                if let Some(next) = self.neighbor(pos, direction)
+                    && !blocked[self.index(next)]
+                {
+                    blocked[self.index(next)] = true;
+                    came_from[self.index(next)] = Some(pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Whether the head of `snake` can still reach its tail.
+    fn tail_reachable(&self, snake: &VecDeque<GridPos>) -> bool {
+        match (snake.front(), snake.back()) {
+            (Some(&head), Some(&tail)) if head != tail => self.path(snake, head, tail).is_some(),
+            _ => true,
+        }
+    }
+
+    /// Number of cells reachable from `from`, the snake's body counting as walls.
+    fn free_area(&self, snake: &VecDeque<GridPos>, from: GridPos) -> usize {
+        let mut seen = self.obstacles.clone();
+        for &pos in snake {
+            seen[self.index(pos)] = true;
+        }
+
+        let mut count = 0;
+        let mut queue = VecDeque::from([from]);
+        while let Some(pos) = queue.pop_front() {
+            count += 1;
+            for direction in Direction::ALL {
+                if let Some(next) = self.neighbor(pos, direction)
+                    && !seen[self.index(next)]
+                {
+                    seen[self.index(next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        count
+    }
+}
+
+/// Moves `snake` one cell to `next`, keeping its tail when it grows.
+fn advance(snake: &mut VecDeque<GridPos>, next: GridPos, grow: bool) {
+    snake.push_front(next);
+    if !grow {
+        snake.pop_back();
+    }
+}
+
+/// Picks the next direction of `player`'s snake. Poison food is steered around.
+pub fn choose_direction(board: &SnakeBoard, player: usize, poison: bool) -> Direction {
+    let planner = Planner::new(board, player, poison);
+    let head = board.snake(player).head();
+    let heading = board.snake(player).heading();
+    // A growing tail stays put: repeat it once per step it waits, so the
+    // searches below keep it blocked until it moves.
+    let mut snake = board.snake(player).body().clone();
+    let tail = *snake.back().unwrap();
+    snake.extend((0..board.snake(player).pending_growth()).map(|_| tail));
+
+    // Take the food if the tail is still reachable once it's eaten.
+    if let Some(food) = board.food().filter(|_| !poison)
+        && let Some(path) = planner.path(&snake, head, food)
+    {
+        let mut future = snake.clone();
+        for (step, &pos) in path.iter().enumerate() {
+            advance(&mut future, pos, step == path.len() - 1);
+        }
+        if planner.tail_reachable(&future)
+            && let Some(direction) = board.direction_between(head, path[0])
+        {
+            return direction;
+        }
+    }
+
+    // Otherwise stay alive: prefer moves that keep the tail in reach, the
+    // longest way round first, then whichever move leaves the most room.
+    let mut best: Option<(bool, usize, Direction)> = None;
+    for direction in Direction::ALL {
+        if direction.is_opposite(heading) && snake.len() > 1 {
+            continue;
+        }
+        let Some(next) = planner.neighbor(head, direction) else {
+            continue;
+        };
+        if snake.iter().take(snake.len() - 1).any(|&pos| pos == next) {
+            continue;
+        }
+
+        let mut future = snake.clone();
+        advance(&mut future, next, false);
+        let tail = *future.back().unwrap();
+        let (safe, score) = match planner.path(&future, next, tail) {
+            Some(path) => (true, path.len()),
+            None => (false, planner.free_area(&future, next)),
+        };
+
+        if best.is_none_or(|(best_safe, best_score, _)| (safe, score) > (best_safe, best_score)) {
+            best = Some((safe, score, direction));
+        }
+    }
+
+    best.map_or(heading, |(_, _, direction)| direction)
+}
+
+/// Queues the bot's choice for each snake it drives, once the previous one is used up.
+fn drive_snake(mut board: ResMut<Board>, attract_mode: Res<AttractMode>, food: Query<&Food>) {
+    let players = board.snakes().len();
+    // The autopilot takes the last snake, so versus games are against the bot.
+    let first_driven = if attract_mode.0 { 0 } else { players - 1 };
+
+    for player in first_driven..players {
+        if board.snake(player).has_queued_turns() {
+            continue;
+        }
+
+        let poison = food.iter().any(|food| food.0 == FoodKind::Poison);
+        let direction = choose_direction(&board, player, poison);
+        board.turn(player, direction);
+    }
+}
+
+fn reset_idle_timer(mut timer: ResMut<IdleTimer>) {
+    timer.0.reset();
+}
+
+fn tick_idle_timer(
+    time: Res<Time>,
+    mut timer: ResMut<IdleTimer>,
+    mut keys: MessageReader<KeyboardInput>,
+) {
+    if keys.read().count() > 0 {
+        timer.0.reset();
+    } else {
+        timer.0.tick(time.delta());
+    }
+}
+
+fn start_attract_mode(
+    timer: Res<IdleTimer>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if timer.0.just_finished() {
+        info!("Menu idle -> Starting the demo");
+        attract_mode.0 = true;
+        next_state.set(AppState::Playing);
+    }
+}
+
+fn stop_attract_mode(mut attract_mode: ResMut<AttractMode>) {
+    attract_mode.0 = false;
+}
+
+/// Leaves once the key is released, so it isn't still down when the menu
+/// opens and read as a menu key.
+fn leave_attract_mode(
+    mut keys: MessageReader<KeyboardInput>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keys.read().any(|key| !key.state.is_pressed()) {
+        next_state.set(AppState::Menu);
+    }
+}
+
+fn back_to_menu(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Menu);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::StepOutcome;
+    use crate::grid::WallRule;
+    use crate::map::LevelMap;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    /// An open 11×9 board, the snake on (3, 4), (2, 4), (1, 4) heading right.
+    fn open_board(wall_rule: WallRule) -> SnakeBoard {
+        SnakeBoard::new(LevelMap::default(), wall_rule)
+    }
+
+    /// The same board with the head moved up to the right edge, on (10, 4).
+    fn board_at_the_edge(wall_rule: WallRule) -> SnakeBoard {
+        let mut board = open_board(wall_rule);
+        for _ in 0..7 {
+            board.step();
+        }
+        board
+    }
+
+    #[test]
+    fn heads_for_the_food() {
+        let mut board = open_board(WallRule::Solid);
+        board.place_food(GridPos { x: 3, y: 7 });
+
+        assert_eq!(choose_direction(&board, 0, false), Direction::Up);
+    }
+
+    #[test]
+    fn turns_away_from_a_solid_wall() {
+        let board = board_at_the_edge(WallRule::Solid);
+
+        let direction = choose_direction(&board, 0, false);
+
+        assert!(matches!(direction, Direction::Up | Direction::Down));
+    }
+
+    #[test]
+    fn uses_the_wrap_around_edge() {
+        let mut board = board_at_the_edge(WallRule::Wrap);
+        board.place_food(GridPos { x: 0, y: 4 });
+
+        assert_eq!(choose_direction(&board, 0, false), Direction::Right);
+    }
+
+    #[test]
+    fn goes_around_the_other_snake() {
+        let map = LevelMap::parse("heading right\n.......\n..ST...\n.......\n").unwrap();
+        let mut board = SnakeBoard::versus(map, WallRule::Solid);
+        board.place_food(GridPos { x: 6, y: 1 });
+
+        let direction = choose_direction(&board, 0, false);
+
+        assert!(matches!(direction, Direction::Up | Direction::Down));
+    }
+
+    #[test]
+    fn steers_around_poison() {
+        let mut board = open_board(WallRule::Solid);
+        board.place_food(GridPos { x: 4, y: 4 });
+
+        assert_eq!(choose_direction(&board, 0, false), Direction::Right);
+        let direction = choose_direction(&board, 0, true);
+        assert!(matches!(direction, Direction::Up | Direction::Down));
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum GameEnd {
+        Crashed,
+        BoardFull,
+        Stuck,
+    }
+
+    /// Plays one game on an open board, returning how it ended and the final length.
+    fn play(wall_rule: WallRule, rng: &mut StdRng) -> (GameEnd, usize) {
+        let mut board = open_board(wall_rule);
+        let size = board.size();
+        let cells = (size.width * size.height) as usize;
+        let mut steps_since_food = 0;
+
+        while let Some(food) = board.random_free_cell(rng) {
+            board.place_food(food);
+            loop {
+                if !board.snake(0).has_queued_turns() {
+                    board.turn(0, choose_direction(&board, 0, false));
+                }
+                match board.step()[0] {
+                    StepOutcome::Died(_) => return (GameEnd::Crashed, board.snake(0).length()),
+                    StepOutcome::Ate => {
+                        board.grow(0, 1);
+                        break;
+                    }
+                    StepOutcome::Moved => {}
+                }
+
+                steps_since_food += 1;
+                if steps_since_food > cells * cells {
+                    return (GameEnd::Stuck, board.snake(0).length());
+                }
+            }
+            steps_since_food = 0;
+        }
+
+        (GameEnd::BoardFull, board.snake(0).length())
+    }
+
+    /// Soak test: `SNAKE_SOAK_GAMES=5000 cargo test -p snake --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "slow, plays thousands of games"]
+    fn soak() {
+        let games: usize = std::env::var("SNAKE_SOAK_GAMES")
+            .ok()
+            .and_then(|games| games.parse().ok())
+            .unwrap_or(2000);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for wall_rule in [WallRule::Solid, WallRule::Wrap] {
+            let results: Vec<(GameEnd, usize)> =
+                (0..games).map(|_| play(wall_rule, &mut rng)).collect();
+            let count = |end: GameEnd| results.iter().filter(|(result, _)| *result == end).count();
+            let average_length =
+                results.iter().map(|(_, length)| *length).sum::<usize>() as f32 / games as f32;
+
+            println!(
+                "{} walls, {games} games: {} crashed, {} stuck, {} filled the board, average length {average_length:.1}",
+                wall_rule.label(),
+                count(GameEnd::Crashed),
+                count(GameEnd::Stuck),
+                count(GameEnd::BoardFull),
+            );
+        }
+    }
+}
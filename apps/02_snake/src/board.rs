@@ -0,0 +1,958 @@
+//! The rules of Snake as plain data: the ECS systems only feed it turns and
+//! steps, then mirror the result onto entities with a [`GridPos`].
+
+use crate::grid::{GridPos, GridSize, WallRule};
+use crate::map::{LevelMap, MapCell};
+use bevy::math::IVec2;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use std::collections::VecDeque;
+
+/// Turns that can be queued ahead of the next steps.
+const TURN_QUEUE_SIZE: usize = 3;
+
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Direction {
+    #[default]
+    Right,
+    Left,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub fn delta(self) -> IVec2 {
+        match self {
+            Direction::Up => IVec2::new(0, 1),
+            Direction::Down => IVec2::new(0, -1),
+            Direction::Left => IVec2::new(-1, 0),
+            Direction::Right => IVec2::new(1, 0),
+        }
+    }
+    pub fn angle(self) -> f32 {
+        match self {
+            Direction::Up => std::f32::consts::FRAC_PI_2,    // 90°
+            Direction::Down => -std::f32::consts::FRAC_PI_2, // -90°
+            Direction::Left => std::f32::consts::PI,         // 180°
+            Direction::Right => 0.0,                         // 0°
+        }
+    }
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+    pub fn is_opposite(self, other: Direction) -> bool {
+        matches!(
+            (self, other),
+            (Direction::Up, Direction::Down)
+                | (Direction::Down, Direction::Up)
+                | (Direction::Left, Direction::Right)
+                | (Direction::Right, Direction::Left)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DeathReason {
+    Wall,
+    OwnBody,
+    Obstacle,
+    /// Versus only: the head ran into the other snake's body.
+    OtherSnake,
+    /// Versus only: both heads met, the round is a draw.
+    HeadOn,
+}
+
+impl DeathReason {
+    pub fn describe(self) -> &'static str {
+        match self {
+            DeathReason::Wall => "You hit a wall",
+            DeathReason::OwnBody => "You bit your own tail",
+            DeathReason::Obstacle => "You hit an obstacle",
+            DeathReason::OtherSnake => "You ran into the other snake",
+            DeathReason::HeadOn => "Head-on crash",
+        }
+    }
+
+    /// The same, to follow a player's name.
+    pub fn summary(self) -> &'static str {
+        match self {
+            DeathReason::Wall => "hit a wall",
+            DeathReason::OwnBody => "bit its own tail",
+            DeathReason::Obstacle => "hit an obstacle",
+            DeathReason::OtherSnake => "ran into the other snake",
+            DeathReason::HeadOn => "crashed head-on",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StepOutcome {
+    Moved,
+    /// The head reached the food, which is gone from the board. What it does
+    /// to the snake is up to the caller, see [`SnakeBoard::grow`] and
+    /// [`SnakeBoard::shrink`].
+    Ate,
+    /// This snake didn't move.
+    Died(DeathReason),
+}
+
+/// What is on a cell, see [`SnakeBoard::occupant`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Occupant {
+    Wall,
+    Obstacle,
+    Portal,
+    Food,
+    /// A segment of this player's snake.
+    Snake(usize),
+}
+
+impl Occupant {
+    /// What the map alone puts on a cell, `None` for the floor.
+    fn of_map(cell: MapCell) -> Option<Self> {
+        match cell {
+            MapCell::Floor => None,
+            MapCell::Wall => Some(Occupant::Wall),
+            MapCell::Obstacle => Some(Occupant::Obstacle),
+            MapCell::Portal => Some(Occupant::Portal),
+        }
+    }
+}
+
+/// How a segment looks, from where its neighbours in the body are.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SegmentShape {
+    Head,
+    /// The body runs straight through, towards the segment ahead.
+    Straight(Direction),
+    /// The body turns, joining the sides towards the segment ahead and behind.
+    Corner {
+        ahead: Direction,
+        behind: Direction,
+    },
+    /// The last segment, pointing at the one ahead.
+    Tail(Direction),
+}
+
+/// One snake on the board, head first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snake {
+    body: VecDeque<GridPos>,
+    heading: Direction,
+    turns: VecDeque<Direction>,
+    pending_growth: u32,
+}
+
+impl Snake {
+    fn new(body: VecDeque<GridPos>, heading: Direction) -> Self {
+        Self {
+            body,
+            heading,
+            turns: VecDeque::new(),
+            pending_growth: 0,
+        }
+    }
+
+    /// A snake as it was saved, with no turns queued. `body` is head first.
+    pub fn restored(
+        body: impl IntoIterator<Item = GridPos>,
+        heading: Direction,
+        pending_growth: u32,
+    ) -> Self {
+        Self {
+            pending_growth,
+            ..Self::new(body.into_iter().collect(), heading)
+        }
+    }
+
+    /// Head first.
+    pub fn body(&self) -> &VecDeque<GridPos> {
+        &self.body
+    }
+
+    pub fn head(&self) -> GridPos {
+        self.body[0]
+    }
+
+    pub fn length(&self) -> usize {
+        self.body.len()
+    }
+
+    /// Direction of the last step.
+    pub fn heading(&self) -> Direction {
+        self.heading
+    }
+
+    pub fn has_queued_turns(&self) -> bool {
+        !self.turns.is_empty()
+    }
+
+    /// Steps left during which the tail stays put.
+    pub fn pending_growth(&self) -> u32 {
+        self.pending_growth
+    }
+
+    /// Whether `pos` is a tail that moves out of the way on the next step.
+    fn leaves(&self, pos: GridPos) -> bool {
+        self.pending_growth == 0 && self.body.back() == Some(&pos)
+    }
+}
+
+/// A board with its map, one snake per player and the food.
+#[derive(Debug, Clone)]
+pub struct SnakeBoard {
+    map: LevelMap,
+    wall_rule: WallRule,
+    snakes: Vec<Snake>,
+    food: Option<GridPos>,
+    /// Row-major like the map, kept up to date as the snakes and the food
+    /// move, so lookups don't scan the bodies.
+    occupancy: Vec<Option<Occupant>>,
+}
+
+impl Default for SnakeBoard {
+    fn default() -> Self {
+        Self::new(LevelMap::default(), WallRule::default())
+    }
+}
+
+impl SnakeBoard {
+    /// A fresh single snake on the map's spawn, with no food placed yet.
+    pub fn new(map: LevelMap, wall_rule: WallRule) -> Self {
+        Self::with_players(map, wall_rule, 1)
+    }
+
+    /// Two snakes, if the map has a second spawn.
+    pub fn versus(map: LevelMap, wall_rule: WallRule) -> Self {
+        Self::with_players(map, wall_rule, 2)
+    }
+
+    fn with_players(map: LevelMap, wall_rule: WallRule, players: usize) -> Self {
+        let snakes = map
+            .spawns()
+            .take(players)
+            .map(|(head, heading)| {
+                Snake::new(LevelMap::segments_behind(head, heading).collect(), heading)
+            })
+            .collect();
+        let occupancy = map
+            .size
+            .cells()
+            .map(|pos| Occupant::of_map(map.cell(pos)))
+            .collect();
+
+        let mut board = Self {
+            map,
+            wall_rule,
+            snakes: Vec::new(),
+            food: None,
+            occupancy,
+        };
+        board.replace_snakes(snakes);
+        board
+    }
+
+    pub fn size(&self) -> GridSize {
+        self.map.size
+    }
+
+    pub fn cell(&self, pos: GridPos) -> MapCell {
+        self.map.cell(pos)
+    }
+
+    /// Indexed by player.
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
+
+    pub fn snake(&self, player: usize) -> &Snake {
+        &self.snakes[player]
+    }
+
+    /// Puts saved snakes in place of the ones on the spawns.
+    pub fn replace_snakes(&mut self, snakes: Vec<Snake>) {
+        for (player, snake) in std::mem::take(&mut self.snakes).into_iter().enumerate() {
+            for pos in snake.body {
+                self.vacate(pos, Occupant::Snake(player));
+            }
+        }
+        for (player, snake) in snakes.iter().enumerate() {
+            for &pos in &snake.body {
+                self.occupy(pos, Occupant::Snake(player));
+            }
+        }
+        self.snakes = snakes;
+    }
+
+    pub fn food(&self) -> Option<GridPos> {
+        self.food
+    }
+
+    /// What is on `pos`, `None` for a free floor cell. Off the board is a wall.
+    pub fn occupant(&self, pos: GridPos) -> Option<Occupant> {
+        match self.index(pos) {
+            Some(index) => self.occupancy[index],
+            None => Some(Occupant::Wall),
+        }
+    }
+
+    fn index(&self, pos: GridPos) -> Option<usize> {
+        let size = self.size();
+        size.contains(pos)
+            .then(|| (pos.y * size.width + pos.x) as usize)
+    }
+
+    fn occupy(&mut self, pos: GridPos, occupant: Occupant) {
+        if let Some(index) = self.index(pos) {
+            self.occupancy[index] = Some(occupant);
+        }
+    }
+
+    /// Puts back what the map has on `pos`, but only if `occupant` is still
+    /// there, so a tail leaving a cell doesn't free it under the head that
+    /// entered it on the same step.
+    fn vacate(&mut self, pos: GridPos, occupant: Occupant) {
+        if let Some(index) = self.index(pos)
+            && self.occupancy[index] == Some(occupant)
+        {
+            self.occupancy[index] = Occupant::of_map(self.map.cell(pos));
+        }
+    }
+
+    /// The cell one step away, after wrapping, or `None` past a solid edge.
+    /// Stepping onto a portal leads to the cell of the linked one instead.
+    pub fn neighbor(&self, pos: GridPos, direction: Direction) -> Option<GridPos> {
+        let delta = direction.delta();
+        let next = GridPos {
+            x: pos.x + delta.x,
+            y: pos.y + delta.y,
+        };
+
+        let next = match self.wall_rule {
+            WallRule::Wrap => self.size().wrap(next),
+            WallRule::Solid => self.size().contains(next).then_some(next)?,
+        };
+        Some(self.map.linked_portal(next).unwrap_or(next))
+    }
+
+    /// The direction of the step from `from` to `to`, if they are neighbours.
+    pub fn direction_between(&self, from: GridPos, to: GridPos) -> Option<Direction> {
+        Direction::ALL
+            .into_iter()
+            .find(|&direction| self.neighbor(from, direction) == Some(to))
+    }
+
+    /// Shape of each of the player's segments, head first.
+    pub fn segment_shapes(&self, player: usize) -> Vec<SegmentShape> {
+        let snake = &self.snakes[player];
+        // The step out of a portal leads back into the cell in front of it,
+        // not to the other end, so it is found from the other side.
+        let towards = |from: GridPos, to: GridPos| {
+            self.direction_between(from, to)
+                .or_else(|| self.direction_between(to, from).map(Direction::opposite))
+                .unwrap_or(snake.heading)
+        };
+
+        let body = &snake.body;
+        (0..body.len())
+            .map(|index| {
+                if index == 0 {
+                    return SegmentShape::Head;
+                }
+                let ahead = towards(body[index], body[index - 1]);
+                match body.get(index + 1) {
+                    None => SegmentShape::Tail(ahead),
+                    Some(&next) => {
+                        let behind = towards(body[index], next);
+                        if behind == ahead.opposite() {
+                            SegmentShape::Straight(ahead)
+                        } else {
+                            SegmentShape::Corner { ahead, behind }
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Queues a turn, checked against the last queued direction so quick inputs
+    /// run in order and can never turn the head back into the neck. Returns
+    /// `false` if the turn was refused.
+    pub fn turn(&mut self, player: usize, direction: Direction) -> bool {
+        let Some(snake) = self.snakes.get_mut(player) else {
+            return false;
+        };
+        let last_direction = snake.turns.back().copied().unwrap_or(snake.heading);
+        if last_direction.is_opposite(direction) || snake.turns.len() >= TURN_QUEUE_SIZE {
+            return false;
+        }
+
+        if last_direction != direction {
+            snake.turns.push_back(direction);
+        }
+        true
+    }
+
+    /// Keeps the tail in place for the next `segments` steps.
+    pub fn grow(&mut self, player: usize, segments: u32) {
+        self.snakes[player].pending_growth += segments;
+    }
+
+    /// Cancels pending growth first, then drops tail cells, never the head.
+    pub fn shrink(&mut self, player: usize, segments: u32) {
+        let snake = &mut self.snakes[player];
+        let cancelled = segments.min(snake.pending_growth);
+        snake.pending_growth -= cancelled;
+
+        let dropped = (segments - cancelled) as usize;
+        let length = snake.body.len().saturating_sub(dropped).max(1);
+        let tail: Vec<GridPos> = snake.body.drain(length..).collect();
+        for pos in tail {
+            self.vacate(pos, Occupant::Snake(player));
+        }
+    }
+
+    /// Floor with nothing on it.
+    pub fn is_free(&self, pos: GridPos) -> bool {
+        self.occupant(pos).is_none()
+    }
+
+    /// Picks uniformly from the free cells, or `None` when the board is full.
+    pub fn random_free_cell(&self, rng: &mut impl Rng) -> Option<GridPos> {
+        let width = self.size().width;
+        let free_cells: Vec<GridPos> = self
+            .occupancy
+            .iter()
+            .enumerate()
+            .filter(|(_, occupant)| occupant.is_none())
+            .map(|(index, _)| GridPos {
+                x: index as i32 % width,
+                y: index as i32 / width,
+            })
+            .collect();
+
+        free_cells.choose(rng).copied()
+    }
+
+    /// Replaces the food on the board, on a cell that should be free.
+    pub fn place_food(&mut self, pos: GridPos) {
+        self.remove_food();
+        self.food = Some(pos);
+        self.occupy(pos, Occupant::Food);
+    }
+
+    pub fn remove_food(&mut self) {
+        if let Some(pos) = self.food.take() {
+            self.vacate(pos, Occupant::Food);
+        }
+    }
+
+    /// Moves every snake one cell at once, after taking its next queued turn.
+    /// Returns one outcome per player.
+    pub fn step(&mut self) -> Vec<StepOutcome> {
+        for snake in &mut self.snakes {
+            if let Some(direction) = snake.turns.pop_front() {
+                snake.heading = direction;
+            }
+        }
+
+        let targets: Vec<Option<GridPos>> = self
+            .snakes
+            .iter()
+            .map(|snake| self.neighbor(snake.head(), snake.heading))
+            .collect();
+        let outcomes: Vec<StepOutcome> = (0..self.snakes.len())
+            .map(|player| match self.collision(player, &targets) {
+                Some(reason) => StepOutcome::Died(reason),
+                None if targets[player] == self.food => StepOutcome::Ate,
+                None => StepOutcome::Moved,
+            })
+            .collect();
+
+        let moves: Vec<(usize, GridPos)> = outcomes
+            .iter()
+            .zip(&targets)
+            .enumerate()
+            .filter_map(|(player, (outcome, &target))| match outcome {
+                StepOutcome::Moved | StepOutcome::Ate => Some((player, target?)),
+                StepOutcome::Died(_) => None,
+            })
+            .collect();
+
+        // Every tail leaves before any head enters, so a head can follow a tail
+        // into its cell on the same step.
+        for &(player, _) in &moves {
+            let snake = &mut self.snakes[player];
+            if snake.pending_growth > 0 {
+                snake.pending_growth -= 1;
+            } else if let Some(tail) = snake.body.pop_back() {
+                self.vacate(tail, Occupant::Snake(player));
+            }
+        }
+        for &(player, next) in &moves {
+            self.snakes[player].body.push_front(next);
+            self.occupy(next, Occupant::Snake(player));
+            if outcomes[player] == StepOutcome::Ate {
+                self.food = None;
+            }
+        }
+
+        outcomes
+    }
+
+    /// What `player` runs into when every snake heads for its cell in `targets`.
+    /// Tails that move this step are out of the way, even if their snake dies.
+    fn collision(&self, player: usize, targets: &[Option<GridPos>]) -> Option<DeathReason> {
+        let Some(next) = targets[player] else {
+            return Some(DeathReason::Wall);
+        };
+        match self.occupant(next) {
+            Some(Occupant::Wall) => return Some(DeathReason::Wall),
+            Some(Occupant::Obstacle) => return Some(DeathReason::Obstacle),
+            _ => {}
+        }
+
+        let head = self.snakes[player].head();
+        for (other, snake) in self.snakes.iter().enumerate() {
+            if other == player {
+                continue;
+            }
+            // Both heads on the same cell, or swapping cells.
+            if targets[other] == Some(next)
+                || (targets[other] == Some(head) && snake.head() == next)
+            {
+                return Some(DeathReason::HeadOn);
+            }
+        }
+
+        match self.occupant(next) {
+            Some(Occupant::Snake(other)) if !self.snakes[other].leaves(next) => {
+                Some(if other == player {
+                    DeathReason::OwnBody
+                } else {
+                    DeathReason::OtherSnake
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The default map: an open 11×9 board, snake on (3, 4), (2, 4), (1, 4) heading right.
+    fn board(wall_rule: WallRule) -> SnakeBoard {
+        SnakeBoard::new(LevelMap::default(), wall_rule)
+    }
+
+    fn pos(x: i32, y: i32) -> GridPos {
+        GridPos { x, y }
+    }
+
+    /// Steps a single-snake board.
+    fn step(board: &mut SnakeBoard) -> StepOutcome {
+        board.step()[0]
+    }
+
+    fn steps(board: &mut SnakeBoard, count: usize) -> StepOutcome {
+        let mut outcome = StepOutcome::Moved;
+        for _ in 0..count {
+            outcome = step(board);
+        }
+        outcome
+    }
+
+    #[test]
+    fn segments_take_their_shape_from_their_neighbours() {
+        let mut board = board(WallRule::Solid);
+        board.turn(0, Direction::Up);
+        step(&mut board);
+
+        // Head on (3, 5), then (3, 4), (2, 4).
+        assert_eq!(
+            board.segment_shapes(0),
+            vec![
+                SegmentShape::Head,
+                SegmentShape::Corner {
+                    ahead: Direction::Up,
+                    behind: Direction::Left
+                },
+                SegmentShape::Tail(Direction::Right),
+            ]
+        );
+        step(&mut board);
+        assert_eq!(
+            board.segment_shapes(0)[1],
+            SegmentShape::Straight(Direction::Up)
+        );
+    }
+
+    #[test]
+    fn segments_connect_through_the_wrap_around_edge() {
+        let mut board = board(WallRule::Wrap);
+        steps(&mut board, 8);
+
+        // Head on (0, 4), then (10, 4), (9, 4).
+        assert_eq!(
+            board.segment_shapes(0),
+            vec![
+                SegmentShape::Head,
+                SegmentShape::Straight(Direction::Right),
+                SegmentShape::Tail(Direction::Right),
+            ]
+        );
+    }
+
+    /// Portal `1` on (4, 1) and (5, 2), the snake on (2, 1), (1, 1), (0, 1)
+    /// heading right.
+    fn portal_board() -> SnakeBoard {
+        let map = LevelMap::parse("heading right\n.....1.\n..S.1..\n.......\n").unwrap();
+        SnakeBoard::new(map, WallRule::Wrap)
+    }
+
+    #[test]
+    fn portals_send_the_head_out_of_the_linked_one() {
+        let mut board = portal_board();
+
+        assert_eq!(steps(&mut board, 2), StepOutcome::Moved);
+        assert_eq!(board.snake(0).head(), pos(5, 2));
+        assert_eq!(board.snake(0).heading(), Direction::Right);
+        assert_eq!(step(&mut board), StepOutcome::Moved);
+        assert_eq!(
+            board.snake(0).body(),
+            &VecDeque::from([pos(6, 2), pos(5, 2), pos(3, 1)])
+        );
+        assert_eq!(
+            board.segment_shapes(0),
+            vec![
+                SegmentShape::Head,
+                SegmentShape::Straight(Direction::Right),
+                SegmentShape::Tail(Direction::Right),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_body_on_the_far_portal_blocks_it() {
+        let mut board = portal_board();
+        board.replace_snakes(vec![Snake::restored(
+            [pos(3, 1), pos(3, 2), pos(4, 2), pos(5, 2), pos(6, 2)],
+            Direction::Right,
+            0,
+        )]);
+
+        assert_eq!(step(&mut board), StepOutcome::Died(DeathReason::OwnBody));
+    }
+
+    #[test]
+    fn solid_wall_kills() {
+        let mut board = board(WallRule::Solid);
+
+        assert_eq!(steps(&mut board, 7), StepOutcome::Moved);
+        assert_eq!(board.snake(0).head(), pos(10, 4));
+        assert_eq!(step(&mut board), StepOutcome::Died(DeathReason::Wall));
+        assert_eq!(board.snake(0).head(), pos(10, 4));
+    }
+
+    #[test]
+    fn wrapping_comes_back_on_the_other_side() {
+        let mut board = board(WallRule::Wrap);
+
+        assert_eq!(steps(&mut board, 8), StepOutcome::Moved);
+        assert_eq!(board.snake(0).head(), pos(0, 4));
+    }
+
+    #[test]
+    fn map_walls_and_obstacles_kill() {
+        let map = LevelMap::parse("heading right\n.......\n..S.o.#\n.......\n").unwrap();
+        let mut board = SnakeBoard::new(map.clone(), WallRule::Wrap);
+        assert_eq!(
+            steps(&mut board, 2),
+            StepOutcome::Died(DeathReason::Obstacle)
+        );
+
+        let mut board = SnakeBoard::new(map, WallRule::Wrap);
+        board.turn(0, Direction::Up);
+        step(&mut board);
+        board.turn(0, Direction::Right);
+        steps(&mut board, 4);
+        board.turn(0, Direction::Down);
+        assert_eq!(step(&mut board), StepOutcome::Died(DeathReason::Wall));
+    }
+
+    #[test]
+    fn turning_into_the_body_kills() {
+        let mut board = board(WallRule::Solid);
+        board.grow(0, 2);
+        steps(&mut board, 2);
+
+        for direction in [Direction::Up, Direction::Left] {
+            board.turn(0, direction);
+            assert_eq!(step(&mut board), StepOutcome::Moved);
+        }
+        board.turn(0, Direction::Down);
+        assert_eq!(step(&mut board), StepOutcome::Died(DeathReason::OwnBody));
+        assert_eq!(board.snake(0).head(), pos(4, 5));
+    }
+
+    #[test]
+    fn turns_for_missing_snakes_are_refused() {
+        let mut board = board(WallRule::Solid);
+
+        assert!(!board.turn(1, Direction::Up));
+        assert!(board.turn(0, Direction::Up));
+    }
+
+    #[test]
+    fn head_can_follow_the_tail_into_its_cell() {
+        let mut board = board(WallRule::Solid);
+        board.grow(0, 1);
+        step(&mut board);
+        assert_eq!(board.snake(0).length(), 4);
+
+        // A 2×2 loop: the head always enters the cell the tail is leaving.
+        for direction in [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ] {
+            board.turn(0, direction);
+            assert_eq!(step(&mut board), StepOutcome::Moved);
+        }
+        assert_eq!(board.snake(0).length(), 4);
+    }
+
+    #[test]
+    fn head_cannot_enter_the_tail_while_growing() {
+        let mut board = board(WallRule::Solid);
+        board.grow(0, 1);
+        step(&mut board);
+
+        board.turn(0, Direction::Up);
+        step(&mut board);
+        board.turn(0, Direction::Left);
+        step(&mut board);
+        board.grow(0, 1);
+        board.turn(0, Direction::Down);
+        assert_eq!(step(&mut board), StepOutcome::Died(DeathReason::OwnBody));
+    }
+
+    #[test]
+    fn eating_grows_on_the_next_step() {
+        let mut board = board(WallRule::Solid);
+        board.place_food(pos(4, 4));
+
+        assert_eq!(step(&mut board), StepOutcome::Ate);
+        assert_eq!(board.food(), None);
+        board.grow(0, 1);
+        assert_eq!(board.snake(0).length(), 3);
+        assert_eq!(step(&mut board), StepOutcome::Moved);
+        assert_eq!(board.snake(0).length(), 4);
+        assert_eq!(board.snake(0).body().back(), Some(&pos(2, 4)));
+    }
+
+    #[test]
+    fn shrinking_cancels_growth_then_drops_the_tail() {
+        let mut board = board(WallRule::Solid);
+        board.grow(0, 1);
+
+        board.shrink(0, 2);
+        assert_eq!(board.snake(0).pending_growth(), 0);
+        assert_eq!(board.snake(0).length(), 2);
+        assert_eq!(board.snake(0).body().back(), Some(&pos(2, 4)));
+
+        board.shrink(0, 5);
+        assert_eq!(board.snake(0).length(), 1);
+    }
+
+    #[test]
+    fn reversing_is_refused() {
+        let mut board = board(WallRule::Solid);
+
+        assert!(!board.turn(0, Direction::Left));
+        assert!(board.turn(0, Direction::Up));
+        assert!(!board.turn(0, Direction::Down));
+        assert!(board.turn(0, Direction::Left));
+        step(&mut board);
+        assert_eq!(board.snake(0).heading(), Direction::Up);
+    }
+
+    #[test]
+    fn meeting_head_on_is_a_draw() {
+        let map = LevelMap::parse("heading right\n.........\n..S...T..\n").unwrap();
+        let mut board = SnakeBoard::versus(map, WallRule::Solid);
+
+        assert_eq!(board.step(), [StepOutcome::Moved, StepOutcome::Moved]);
+        assert_eq!(board.step(), [StepOutcome::Died(DeathReason::HeadOn); 2]);
+    }
+
+    #[test]
+    fn heads_cannot_swap_cells() {
+        let map = LevelMap::parse("heading right\n.........\n..S..T...\n").unwrap();
+        let mut board = SnakeBoard::versus(map, WallRule::Solid);
+
+        assert_eq!(board.step(), [StepOutcome::Moved, StepOutcome::Moved]);
+        assert_eq!(board.step(), [StepOutcome::Died(DeathReason::HeadOn); 2]);
+    }
+
+    #[test]
+    fn running_into_the_other_snake_loses() {
+        let map = LevelMap::parse("heading right\n.......\n....T..\n..S....\n").unwrap();
+        let mut board = SnakeBoard::versus(map, WallRule::Solid);
+        board.step();
+
+        board.turn(0, Direction::Up);
+        assert_eq!(
+            board.step(),
+            [
+                StepOutcome::Died(DeathReason::OtherSnake),
+                StepOutcome::Moved
+            ]
+        );
+        assert_eq!(board.snake(0).head(), pos(3, 0));
+        assert_eq!(board.snake(1).head(), pos(2, 1));
+    }
+
+    #[test]
+    fn head_can_follow_the_other_tail() {
+        let map = LevelMap::parse("heading right\n..T....\n....S..\n").unwrap();
+        let mut board = SnakeBoard::versus(map.clone(), WallRule::Solid);
+        board.turn(0, Direction::Up);
+        assert_eq!(board.step(), [StepOutcome::Moved, StepOutcome::Moved]);
+        assert_eq!(board.snake(0).head(), pos(4, 1));
+
+        let mut board = SnakeBoard::versus(map, WallRule::Solid);
+        board.grow(1, 1);
+        board.turn(0, Direction::Up);
+        assert_eq!(
+            board.step(),
+            [
+                StepOutcome::Died(DeathReason::OtherSnake),
+                StepOutcome::Moved
+            ]
+        );
+    }
+
+    #[test]
+    fn versus_needs_a_second_spawn() {
+        let board = SnakeBoard::versus(
+            LevelMap::parse("heading up\n.\nS\n.\n.\n").unwrap(),
+            WallRule::Solid,
+        );
+
+        assert_eq!(board.snakes().len(), 1);
+    }
+
+    #[test]
+    fn nearly_full_board_returns_the_only_free_cell() {
+        let mut board = board(WallRule::Solid);
+        let free = pos(7, 2);
+        let size = board.size();
+        board.replace_snakes(vec![Snake::restored(
+            size.cells().filter(|&cell| cell != free),
+            Direction::Right,
+            0,
+        )]);
+
+        let mut rng = rand::rng();
+        for _ in 0..20 {
+            assert_eq!(board.random_free_cell(&mut rng), Some(free));
+        }
+    }
+
+    #[test]
+    fn full_board_has_no_free_cell() {
+        let mut board = board(WallRule::Solid);
+        let size = board.size();
+        board.replace_snakes(vec![Snake::restored(size.cells(), Direction::Right, 0)]);
+
+        assert_eq!(board.random_free_cell(&mut rand::rng()), None);
+    }
+
+    #[test]
+    fn free_cell_is_never_taken() {
+        let mut board = board(WallRule::Solid);
+        let size = board.size();
+        board.replace_snakes(vec![Snake::restored(
+            size.cells().step_by(2),
+            Direction::Right,
+            0,
+        )]);
+
+        let mut rng = rand::rng();
+        for _ in 0..100 {
+            let cell = board.random_free_cell(&mut rng).unwrap();
+            assert!(!board.snake(0).body().contains(&cell));
+        }
+    }
+
+    #[test]
+    fn occupancy_follows_the_snake_and_the_food() {
+        let mut board = board(WallRule::Solid);
+        board.place_food(pos(4, 4));
+        assert_eq!(board.occupant(pos(4, 4)), Some(Occupant::Food));
+
+        step(&mut board);
+        board.grow(0, 1);
+        step(&mut board);
+        assert_eq!(board.occupant(pos(5, 4)), Some(Occupant::Snake(0)));
+        assert_eq!(board.occupant(pos(2, 4)), Some(Occupant::Snake(0)));
+        assert!(board.is_free(pos(1, 4)));
+
+        board.shrink(0, 2);
+        assert!(board.is_free(pos(2, 4)));
+        assert!(board.is_free(pos(3, 4)));
+        assert_eq!(board.occupant(pos(4, 4)), Some(Occupant::Snake(0)));
+
+        board.place_food(pos(0, 0));
+        board.place_food(pos(1, 0));
+        assert!(board.is_free(pos(0, 0)));
+        board.remove_food();
+        assert!(board.is_free(pos(1, 0)));
+    }
+
+    #[test]
+    fn a_tail_leaving_frees_its_cell_on_the_same_step() {
+        let map = LevelMap::parse("heading right\n..T....\n....S..\n").unwrap();
+        let mut board = SnakeBoard::versus(map, WallRule::Solid);
+        board.turn(0, Direction::Up);
+        board.step();
+
+        // The first head took the cell the second tail left.
+        assert_eq!(board.occupant(pos(4, 1)), Some(Occupant::Snake(0)));
+        assert!(board.is_free(pos(2, 0)));
+    }
+
+    #[test]
+    fn portals_and_map_cells_are_never_free() {
+        let board = portal_board();
+
+        assert_eq!(board.occupant(pos(4, 1)), Some(Occupant::Portal));
+        assert_eq!(board.occupant(pos(-1, 0)), Some(Occupant::Wall));
+        assert!(!board.is_free(pos(5, 2)));
+    }
+
+    #[test]
+    fn replaced_snakes_free_the_old_cells() {
+        let mut board = board(WallRule::Solid);
+        board.replace_snakes(vec![Snake::restored([pos(6, 6)], Direction::Up, 0)]);
+
+        assert!(board.is_free(pos(3, 4)));
+        assert_eq!(board.occupant(pos(6, 6)), Some(Occupant::Snake(0)));
+    }
+}
@@ -1,30 +1,152 @@
-use crate::grid::{grid_to_world, random_grid_pos, GridPos, TILE_SIZE};
-use crate::player::SnakeSegments;
-use crate::{AppState, AssetState};
+//! Food items. Each kind has its own effect and spawn weight, see [`FOOD_TYPES`].
+
+use crate::board::SnakeBoard;
+use crate::grid::{GridPos, GridSize, TILE_SIZE};
+use crate::player::Board;
+use crate::replay::Playback;
+use crate::save::{ResumedGame, restore_board};
+use crate::seed::GameRng;
+use crate::{AppState, AppSystems, AssetState, PausableSystems};
 use bevy::app::App;
-use bevy::color::palettes::tailwind::RED_400;
+use bevy::color::palettes::tailwind::{AMBER_400, PURPLE_500, RED_400, SKY_400};
 use bevy::prelude::*;
+use rand::Rng;
+use rand::seq::IndexedRandom;
 
 const FOOD_Z: f32 = 1.0;
 
+/// Food definitions, in the same order as [`FoodKind`].
+pub const FOOD_TYPES: &[FoodType] = &[
+    FoodType {
+        kind: FoodKind::Normal,
+        weight: 10,
+        growth: 1,
+        points: 1,
+        lifetime: None,
+        color: Color::Srgba(RED_400),
+        sound_speed: 1.0,
+    },
+    FoodType {
+        kind: FoodKind::Golden,
+        weight: 2,
+        growth: 1,
+        points: 5,
+        lifetime: Some(5.0),
+        color: Color::Srgba(AMBER_400),
+        sound_speed: 1.5,
+    },
+    FoodType {
+        kind: FoodKind::Poison,
+        weight: 2,
+        growth: -2,
+        points: 0,
+        lifetime: Some(8.0),
+        color: Color::Srgba(PURPLE_500),
+        sound_speed: 0.6,
+    },
+    FoodType {
+        kind: FoodKind::Speed,
+        weight: 2,
+        growth: 1,
+        points: 1,
+        lifetime: None,
+        color: Color::Srgba(SKY_400),
+        sound_speed: 1.25,
+    },
+];
+
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(AssetState::Loading), setup);
     app.add_observer(on_food_eaten);
+    app.add_observer(replace_expired_food);
+
+    app.add_systems(
+        OnEnter(AppState::Playing),
+        (
+            spawn_food.run_if(not(resource_exists::<ResumedGame>)),
+            restore_food.run_if(resource_exists::<ResumedGame>),
+        )
+            .after(restore_board),
+    );
+    app.add_systems(
+        Update,
+        tick_food_lifetime
+            .run_if(in_state(AppState::Playing))
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        // Replays expire food when the recording says so.
+        expire_food
+            .run_if(in_state(AppState::Playing).and(not(resource_exists::<Playback>)))
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        shrink_expiring_food
+            .run_if(in_state(AppState::Playing))
+            .in_set(AppSystems::Render),
+    );
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FoodKind {
+    Normal,
+    /// Worth more, but only for a few seconds.
+    Golden,
+    /// Shrinks the snake.
+    Poison,
+    /// Speeds the snake up for a while.
+    Speed,
+}
 
-    app.add_systems(OnEnter(AppState::Playing), spawn_food);
+impl FoodKind {
+    pub fn food_type(self) -> &'static FoodType {
+        &FOOD_TYPES[self as usize]
+    }
+}
+
+#[derive(Debug)]
+pub struct FoodType {
+    pub kind: FoodKind,
+    /// Relative chance of being the next food.
+    pub weight: u32,
+    /// Segments added, or removed when negative.
+    pub growth: i32,
+    /// Multiplies the points of a normal food.
+    pub points: u32,
+    /// Seconds before it disappears, `None` to stay until eaten.
+    pub lifetime: Option<f32>,
+    pub color: Color,
+    /// Playback speed of the eating sound, higher is higher pitched.
+    pub sound_speed: f32,
 }
 
 #[derive(Component)]
-pub struct Food;
+pub struct Food(pub FoodKind);
+
+/// Countdown of a food with a [`FoodType::lifetime`].
+#[derive(Component)]
+struct FoodLifetime(Timer);
 
 #[derive(Resource)]
 struct FoodAssets {
     mesh: Handle<Mesh>,
-    material: Handle<ColorMaterial>,
+    /// Indexed by [`FoodKind`].
+    materials: Vec<Handle<ColorMaterial>>,
+}
+
+#[derive(Event)]
+pub struct FoodEaten {
+    pub player: usize,
+    pub kind: FoodKind,
 }
 
+/// The timed food ran out and is replaced.
 #[derive(Event)]
-pub struct FoodEaten;
+pub struct FoodExpired;
 
 fn setup(
     mut commands: Commands,
@@ -34,46 +156,142 @@ fn setup(
     let food_size = TILE_SIZE * 0.8;
 
     let mesh = meshes.add(Rectangle::new(food_size, food_size));
-    let material = materials.add(ColorMaterial::from(Color::from(RED_400)));
+    let materials = FOOD_TYPES
+        .iter()
+        .map(|food_type| materials.add(ColorMaterial::from(food_type.color)))
+        .collect();
 
-    commands.insert_resource(FoodAssets { mesh, material });
+    commands.insert_resource(FoodAssets { mesh, materials });
 }
 
 fn on_food_eaten(
     _event: On<FoodEaten>,
     commands: Commands,
-    snake_segments: Res<SnakeSegments>,
-    positions: Query<&GridPos>,
+    board: ResMut<Board>,
+    size: Res<GridSize>,
     food_assets: Res<FoodAssets>,
+    rng: ResMut<GameRng>,
+    next_state: ResMut<NextState<AppState>>,
 ) {
-    spawn_food(commands, snake_segments, positions, food_assets);
+    spawn_food(commands, board, size, food_assets, rng, next_state);
 }
 
 fn spawn_food(
     mut commands: Commands,
-    snake_segments: Res<SnakeSegments>,
-    positions: Query<&GridPos>,
+    mut board: ResMut<Board>,
+    size: Res<GridSize>,
     food_assets: Res<FoodAssets>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<AppState>>,
 ) {
-    let occupied: Vec<GridPos> = snake_segments
-        .0
-        .iter()
-        .filter_map(|&entity| positions.get(entity).ok())
-        .copied()
-        .collect();
+    let Some((pos, kind)) = place_random_food(&mut board, &mut **rng) else {
+        info!("No free cell left -> Board cleared!");
+        next_state.set(AppState::Won);
+        return;
+    };
+    insert_food(&mut commands, &food_assets, *size, pos, kind);
+}
+
+/// Puts the saved game's food back instead of a random one. Timed food starts
+/// its countdown over.
+fn restore_food(
+    mut commands: Commands,
+    mut board: ResMut<Board>,
+    size: Res<GridSize>,
+    food_assets: Res<FoodAssets>,
+    resumed: Res<ResumedGame>,
+) {
+    let (pos, kind) = resumed.food;
+    board.place_food(pos);
+    insert_food(&mut commands, &food_assets, *size, pos, kind);
+}
+
+fn insert_food(
+    commands: &mut Commands,
+    food_assets: &FoodAssets,
+    size: GridSize,
+    pos: GridPos,
+    kind: FoodKind,
+) {
+    let food_type = kind.food_type();
+    let mut food = commands.spawn((
+        Food(food_type.kind),
+        pos,
+        Mesh2d(food_assets.mesh.clone()),
+        MeshMaterial2d(food_assets.materials[food_type.kind as usize].clone()),
+        Transform::from_translation(size.to_world(pos).with_z(FOOD_Z)),
+        DespawnOnExit(AppState::Playing),
+    ));
+    if let Some(lifetime) = food_type.lifetime {
+        food.insert(FoodLifetime(Timer::from_seconds(lifetime, TimerMode::Once)));
+    }
+}
+
+/// Puts a food of a random kind on a random free cell, `None` once the board
+/// is full. Replays rely on it drawing from `rng` in the same order every time.
+pub fn place_random_food(
+    board: &mut SnakeBoard,
+    rng: &mut impl Rng,
+) -> Option<(GridPos, FoodKind)> {
+    let pos = board.random_free_cell(rng)?;
+    let kind = FOOD_TYPES
+        .choose_weighted(rng, |food_type| food_type.weight)
+        .map_or(FoodKind::Normal, |food_type| food_type.kind);
+
+    board.place_food(pos);
+    Some((pos, kind))
+}
+
+fn tick_food_lifetime(time: Res<Time>, mut lifetimes: Query<&mut FoodLifetime>) {
+    for mut lifetime in &mut lifetimes {
+        lifetime.0.tick(time.delta());
+    }
+}
+
+fn expire_food(mut commands: Commands, lifetimes: Query<&FoodLifetime>) {
+    if lifetimes.iter().any(|lifetime| lifetime.0.just_finished()) {
+        commands.trigger(FoodExpired);
+    }
+}
+
+/// Replaces a food whose countdown ran out.
+fn replace_expired_food(
+    _event: On<FoodExpired>,
+    mut commands: Commands,
+    foods: Query<(Entity, &Food), With<FoodLifetime>>,
+    mut board: ResMut<Board>,
+    size: Res<GridSize>,
+    food_assets: Res<FoodAssets>,
+    rng: ResMut<GameRng>,
+    next_state: ResMut<NextState<AppState>>,
+) {
+    let Some((entity, food)) = foods.iter().next() else {
+        return;
+    };
+
+    info!("The {:?} food is gone", food.0);
+    commands.entity(entity).despawn();
+    board.remove_food();
+    spawn_food(commands, board, size, food_assets, rng, next_state);
+}
+
+/// Shrinks timed food as its countdown runs out.
+fn shrink_expiring_food(mut foods: Query<(&FoodLifetime, &mut Transform)>) {
+    for (lifetime, mut transform) in &mut foods {
+        let scale = 0.4 + 0.6 * lifetime.0.fraction_remaining();
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    loop {
-        let pos = random_grid_pos();
-        if !occupied.contains(&pos) {
-            commands.spawn((
-                Food,
-                pos,
-                Mesh2d(food_assets.mesh.clone()),
-                MeshMaterial2d(food_assets.material.clone()),
-                Transform::from_translation(grid_to_world(pos).with_z(FOOD_Z)),
-                DespawnOnExit(AppState::Playing),
-            ));
-            break;
+    #[test]
+    fn table_follows_the_kind_order() {
+        for (index, food_type) in FOOD_TYPES.iter().enumerate() {
+            assert_eq!(food_type.kind as usize, index);
+            assert_eq!(food_type.kind.food_type().kind, food_type.kind);
         }
     }
 }
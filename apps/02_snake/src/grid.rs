@@ -1,89 +1,348 @@
-use crate::{AppState, AppSystems};
+use crate::level::load_level_map;
+use crate::map::{ActiveMap, MapCell};
+use crate::{AppState, AppSystems, WORLD_HEIGHT, WORLD_WIDTH};
 use bevy::asset::Assets;
 use bevy::mesh::Mesh;
 use bevy::prelude::*;
-use rand::RngExt;
 
 pub const GRID_LIGHT_COLOR: Color = Color::srgb_u8(170, 215, 81);
 pub const GRID_DARK_COLOR: Color = Color::srgb_u8(162, 209, 73);
+const WALL_COLOR: Color = Color::srgb_u8(87, 83, 78);
+const OBSTACLE_COLOR: Color = Color::srgb_u8(120, 113, 108);
+const PORTAL_COLOR: Color = Color::srgb_u8(139, 92, 246);
 pub const TILE_SIZE: f32 = 64.0;
-pub const GRID_WIDTH: u32 = 11;
-pub const GRID_HEIGHT: u32 = 9;
 pub const GRID_Z: f32 = 0.0;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(AppState::Playing), spawn_grid);
+    app.init_resource::<GridSize>();
+    app.init_resource::<WallRule>();
+    app.init_resource::<BoardSize>();
+
+    app.add_systems(OnEnter(AppState::Playing), spawn_grid.after(load_level_map));
     app.add_systems(
         Update,
         sync_grid_to_transform
             .run_if(in_state(AppState::Playing))
             .in_set(AppSystems::Render),
     );
+    app.add_systems(
+        Update,
+        fit_camera_to_board
+            .run_if(resource_changed::<GridSize>)
+            .in_set(AppSystems::Render),
+    );
 }
 
 #[derive(Component)]
 struct Tile;
 
-#[derive(Component, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Component, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub struct GridPos {
     pub x: i32,
     pub y: i32,
 }
 
-pub fn random_grid_pos() -> GridPos {
-    let mut rng = rand::rng();
-    GridPos {
-        x: rng.random_range(0..GRID_WIDTH as i32),
-        y: rng.random_range(0..GRID_HEIGHT as i32),
+/// Cell a moving entity left on the last step. Its `Transform` slides from
+/// there to its [`GridPos`] instead of snapping to it.
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PreviousGridPos(pub GridPos);
+
+/// Board dimensions in cells, taken from the current map.
+#[derive(Resource, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct GridSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Default for GridSize {
+    fn default() -> Self {
+        Self {
+            width: 11,
+            height: 9,
+        }
+    }
+}
+
+impl GridSize {
+    pub fn contains(self, pos: GridPos) -> bool {
+        pos.x >= 0 && pos.x < self.width && pos.y >= 0 && pos.y < self.height
+    }
+
+    /// Brings a position that left the board back in on the opposite edge.
+    pub fn wrap(self, pos: GridPos) -> GridPos {
+        GridPos {
+            x: pos.x.rem_euclid(self.width),
+            y: pos.y.rem_euclid(self.height),
+        }
+    }
+
+    /// Off-board positions are wrapped onto the board, so nothing is ever drawn outside it.
+    pub fn to_world(self, pos: GridPos) -> Vec3 {
+        let pos = self.wrap(pos);
+        let board_width = self.width as f32 * TILE_SIZE;
+        let board_height = self.height as f32 * TILE_SIZE;
+
+        Vec3::new(
+            pos.x as f32 * TILE_SIZE - board_width / 2.0 + TILE_SIZE / 2.0,
+            pos.y as f32 * TILE_SIZE - board_height / 2.0 + TILE_SIZE / 2.0,
+            GRID_Z,
+        )
+    }
+
+    /// World position `fraction` of the way through a one-cell `step` from
+    /// `from` that ended on `to`. It slides out of `from` over the first half
+    /// and into `to` over the second, so through the wrap-around edge or a
+    /// portal it is never more than half a tile away from either cell.
+    pub fn interpolate(self, from: GridPos, to: GridPos, step: IVec2, fraction: f32) -> Vec3 {
+        let step = (step.as_vec2() * TILE_SIZE).extend(0.0);
+        if fraction < 0.5 {
+            self.to_world(from) + step * fraction
+        } else {
+            self.to_world(to) - step * (1.0 - fraction)
+        }
+    }
+
+    pub fn cells(self) -> impl Iterator<Item = GridPos> {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| GridPos { x, y }))
+    }
+}
+
+/// Board size chosen in the menu. Level maps smaller than the board are
+/// centred on it, the board never gets smaller than the map.
+#[derive(Resource, Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BoardSize {
+    /// Each level's own map size.
+    #[default]
+    Level,
+    Small,
+    Medium,
+    Large,
+    Custom(GridSize),
+}
+
+impl BoardSize {
+    pub const MIN: GridSize = GridSize {
+        width: 7,
+        height: 7,
+    };
+    pub const MAX: GridSize = GridSize {
+        width: 41,
+        height: 25,
+    };
+
+    /// The next size in the menu, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            BoardSize::Level => BoardSize::Small,
+            BoardSize::Small => BoardSize::Medium,
+            BoardSize::Medium => BoardSize::Large,
+            BoardSize::Large => BoardSize::Custom(GridSize {
+                width: 15,
+                height: 11,
+            }),
+            BoardSize::Custom(_) => BoardSize::Level,
+        }
+    }
+
+    /// `None` for [`BoardSize::Level`].
+    pub fn size(self) -> Option<GridSize> {
+        match self {
+            BoardSize::Level => None,
+            BoardSize::Small => Some(GridSize::default()),
+            BoardSize::Medium => Some(GridSize {
+                width: 17,
+                height: 11,
+            }),
+            BoardSize::Large => Some(GridSize {
+                width: 25,
+                height: 15,
+            }),
+            BoardSize::Custom(size) => Some(size),
+        }
+    }
+
+    /// Switches to a custom size, changed by `width` and `height` cells and
+    /// kept between [`BoardSize::MIN`] and [`BoardSize::MAX`].
+    pub fn resized(self, width: i32, height: i32) -> Self {
+        let size = self.size().unwrap_or_default();
+        BoardSize::Custom(GridSize {
+            width: (size.width + width).clamp(Self::MIN.width, Self::MAX.width),
+            height: (size.height + height).clamp(Self::MIN.height, Self::MAX.height),
+        })
+    }
+
+    pub fn label(self) -> String {
+        let name = match self {
+            BoardSize::Level => return "level size".to_string(),
+            BoardSize::Small => "small",
+            BoardSize::Medium => "medium",
+            BoardSize::Large => "large",
+            BoardSize::Custom(_) => "custom",
+        };
+        let size = self.size().unwrap_or_default();
+        format!("{name} ({}x{})", size.width, size.height)
     }
 }
 
-pub fn grid_to_world(pos: GridPos) -> Vec3 {
-    let board_width = GRID_WIDTH as f32 * TILE_SIZE;
-    let board_height = GRID_HEIGHT as f32 * TILE_SIZE;
+/// What happens when the snake's head leaves the board.
+#[derive(Resource, Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum WallRule {
+    /// Leaving the board ends the game.
+    #[default]
+    Solid,
+    /// The head comes back in on the opposite edge.
+    Wrap,
+}
 
-    Vec3::new(
-        pos.x as f32 * TILE_SIZE - board_width / 2.0 + TILE_SIZE / 2.0,
-        pos.y as f32 * TILE_SIZE - board_height / 2.0 + TILE_SIZE / 2.0,
-        GRID_Z,
-    )
+impl WallRule {
+    pub fn toggled(self) -> Self {
+        match self {
+            WallRule::Solid => WallRule::Wrap,
+            WallRule::Wrap => WallRule::Solid,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WallRule::Solid => "solid",
+            WallRule::Wrap => "wrap-around",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        [WallRule::Solid, WallRule::Wrap]
+            .into_iter()
+            .find(|wall_rule| wall_rule.label() == label)
+    }
 }
 
-fn spawn_grid(
+pub fn spawn_grid(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    map: Res<ActiveMap>,
 ) {
     // One shared quad mesh
     let tile_mesh = meshes.add(Rectangle::new(TILE_SIZE, TILE_SIZE));
 
-    // Two shared materials
+    // Shared materials
     let light = materials.add(GRID_LIGHT_COLOR);
     let dark = materials.add(GRID_DARK_COLOR);
+    let wall = materials.add(WALL_COLOR);
+    let obstacle = materials.add(OBSTACLE_COLOR);
+    let portal = materials.add(PORTAL_COLOR);
 
-    for y in 0..GRID_HEIGHT as i32 {
-        for x in 0..GRID_WIDTH as i32 {
-            let grid_pos = GridPos { x, y };
-            let is_light = (x + y) % 2 == 0;
-
-            commands.spawn((
-                Tile,
-                grid_pos,
-                Mesh2d(tile_mesh.clone()),
-                MeshMaterial2d(if is_light {
-                    light.clone()
-                } else {
-                    dark.clone()
-                }),
-                Transform::from_translation(grid_to_world(grid_pos)),
-                DespawnOnExit(AppState::Playing),
-            ));
-        }
+    let size = map.0.size;
+    for grid_pos in size.cells() {
+        let material = match map.0.cell(grid_pos) {
+            MapCell::Floor if (grid_pos.x + grid_pos.y) % 2 == 0 => light.clone(),
+            MapCell::Floor => dark.clone(),
+            MapCell::Wall => wall.clone(),
+            MapCell::Obstacle => obstacle.clone(),
+            MapCell::Portal => portal.clone(),
+        };
+
+        commands.spawn((
+            Tile,
+            grid_pos,
+            Mesh2d(tile_mesh.clone()),
+            MeshMaterial2d(material),
+            Transform::from_translation(size.to_world(grid_pos)),
+            DespawnOnExit(AppState::Playing),
+        ));
     }
 }
 
-fn sync_grid_to_transform(mut query: Query<(&GridPos, &mut Transform), Changed<GridPos>>) {
+/// Zooms the camera out when the board doesn't fit the window, keeping a tile
+/// to spare at the top and bottom for the HUD.
+fn fit_camera_to_board(
+    size: Res<GridSize>,
+    mut projection: Single<&mut Projection, With<Camera2d>>,
+) {
+    let Projection::Orthographic(orthographic) = &mut **projection else {
+        return;
+    };
+
+    let board_width = (size.width + 1) as f32 * TILE_SIZE;
+    let board_height = (size.height + 2) as f32 * TILE_SIZE;
+    orthographic.scale = (board_width / WORLD_WIDTH as f32)
+        .max(board_height / WORLD_HEIGHT as f32)
+        .max(1.0);
+}
+
+/// Snaps still entities to their cell, moving ones are interpolated instead.
+fn sync_grid_to_transform(
+    size: Res<GridSize>,
+    mut query: Query<(&GridPos, &mut Transform), (Changed<GridPos>, Without<PreviousGridPos>)>,
+) {
     for (grid, mut transform) in &mut query {
-        transform.translation = grid_to_world(*grid).with_z(transform.translation.z);
+        transform.translation = size.to_world(*grid).with_z(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_board_size_stays_in_bounds() {
+        let size = BoardSize::Small.resized(100, -100);
+
+        assert_eq!(
+            size.size(),
+            Some(GridSize {
+                width: BoardSize::MAX.width,
+                height: BoardSize::MIN.height
+            })
+        );
+    }
+
+    #[test]
+    fn interpolates_between_neighbours() {
+        let size = GridSize::default();
+        let from = GridPos { x: 3, y: 4 };
+        let to = GridPos { x: 3, y: 5 };
+
+        assert_eq!(
+            size.interpolate(from, to, IVec2::Y, 0.0),
+            size.to_world(from)
+        );
+        assert_eq!(
+            size.interpolate(from, to, IVec2::Y, 0.25),
+            size.to_world(from) + Vec3::Y * TILE_SIZE * 0.25
+        );
+        assert_eq!(size.interpolate(from, to, IVec2::Y, 1.0), size.to_world(to));
+    }
+
+    #[test]
+    fn slides_through_the_wrap_around_edge() {
+        let size = GridSize::default();
+        let from = GridPos { x: 10, y: 4 };
+        let to = GridPos { x: 0, y: 4 };
+
+        assert_eq!(
+            size.interpolate(from, to, IVec2::X, 0.25),
+            size.to_world(from) + Vec3::X * TILE_SIZE * 0.25
+        );
+        assert_eq!(
+            size.interpolate(from, to, IVec2::X, 0.75),
+            size.to_world(to) - Vec3::X * TILE_SIZE * 0.25
+        );
+    }
+
+    #[test]
+    fn jumps_between_portals_halfway_through_the_step() {
+        let size = GridSize::default();
+        let from = GridPos { x: 2, y: 1 };
+        let to = GridPos { x: 8, y: 6 };
+
+        assert_eq!(
+            size.interpolate(from, to, IVec2::NEG_Y, 0.25),
+            size.to_world(from) - Vec3::Y * TILE_SIZE * 0.25
+        );
+        assert_eq!(
+            size.interpolate(from, to, IVec2::NEG_Y, 0.75),
+            size.to_world(to) + Vec3::Y * TILE_SIZE * 0.25
+        );
     }
 }
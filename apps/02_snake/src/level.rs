@@ -0,0 +1,181 @@
+//! Levels: each one has its own speed curve, food target and map.
+
+use crate::AppState;
+use crate::board::SnakeBoard;
+use crate::food::FoodEaten;
+use crate::grid::{BoardSize, GridSize, WallRule};
+use crate::map::{ActiveMap, LevelMap, MapAssets};
+use crate::player::{Board, GameMode};
+use bevy::prelude::*;
+
+/// Level definitions, played in order. Each one is laid out by its map asset.
+pub const LEVELS: &[Level] = &[
+    Level {
+        speed: SpeedCurve {
+            start: 0.24,
+            step: 0.005,
+            floor: 0.14,
+        },
+        food_target: Some(5),
+        map: "maps/level1.map",
+    },
+    Level {
+        speed: SpeedCurve {
+            start: 0.22,
+            step: 0.005,
+            floor: 0.12,
+        },
+        food_target: Some(8),
+        map: "maps/level2.map",
+    },
+    Level {
+        speed: SpeedCurve {
+            start: 0.2,
+            step: 0.005,
+            floor: 0.1,
+        },
+        food_target: Some(10),
+        map: "maps/level3.map",
+    },
+    Level {
+        speed: SpeedCurve {
+            start: 0.18,
+            step: 0.004,
+            floor: 0.08,
+        },
+        food_target: None,
+        map: "maps/level4.map",
+    },
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CurrentLevel>();
+    app.init_resource::<LevelProgress>();
+    app.add_observer(advance_level);
+
+    app.add_systems(
+        OnTransition {
+            exited: AppState::Menu,
+            entered: AppState::Playing,
+        },
+        reset_level,
+    );
+    app.add_systems(OnEnter(AppState::Playing), (reset_progress, load_level_map));
+}
+
+/// Move interval in seconds: shrinks by `step` for every segment past the
+/// starting length, down to `floor`.
+#[derive(Debug, Clone, Copy)]
+pub struct SpeedCurve {
+    pub start: f32,
+    pub step: f32,
+    pub floor: f32,
+}
+
+impl SpeedCurve {
+    pub fn interval(&self, extra_segments: usize) -> f32 {
+        (self.start - self.step * extra_segments as f32).max(self.floor)
+    }
+}
+
+#[derive(Debug)]
+pub struct Level {
+    pub speed: SpeedCurve,
+    /// Food to eat before the next level, `None` for the last one.
+    pub food_target: Option<u32>,
+    /// Path of the level's map, see [`crate::map`].
+    pub map: &'static str,
+}
+
+/// Index into [`LEVELS`].
+#[derive(Resource, Default)]
+pub struct CurrentLevel(pub usize);
+
+impl CurrentLevel {
+    pub fn get(&self) -> &'static Level {
+        &LEVELS[self.0]
+    }
+
+    /// Level number shown to the player, starting at 1.
+    pub fn number(&self) -> usize {
+        self.0 + 1
+    }
+}
+
+/// Food eaten in the current level, poison aside.
+#[derive(Resource, Default)]
+pub struct LevelProgress(pub u32);
+
+fn reset_level(mut level: ResMut<CurrentLevel>) {
+    level.0 = 0;
+}
+
+pub fn reset_progress(mut progress: ResMut<LevelProgress>) {
+    progress.0 = 0;
+}
+
+/// Sets up a fresh board from the current level's map, before anything is spawned on it.
+pub fn load_level_map(
+    level: Res<CurrentLevel>,
+    map_assets: Res<MapAssets>,
+    maps: Res<Assets<LevelMap>>,
+    mut active_map: ResMut<ActiveMap>,
+    wall_rule: Res<WallRule>,
+    mode: Res<GameMode>,
+    board_size: Res<BoardSize>,
+    mut size: ResMut<GridSize>,
+    mut board: ResMut<Board>,
+) {
+    let map = map_assets
+        .maps
+        .get(level.get().map)
+        .and_then(|handle| maps.get(handle))
+        .cloned()
+        .unwrap_or_else(|| {
+            warn!("Map {} is not loaded, using an open board", level.get().map);
+            LevelMap::default()
+        });
+    let map = match board_size.size() {
+        Some(board_size) => map.fitted(board_size),
+        None => map,
+    };
+
+    *size = map.size;
+    board.0 = match *mode {
+        GameMode::Solo => SnakeBoard::new(map.clone(), *wall_rule),
+        GameMode::Versus => {
+            if map.second_spawn.is_none() {
+                warn!(
+                    "Map {} has no second spawn `T`, playing solo",
+                    level.get().map
+                );
+            }
+            SnakeBoard::versus(map.clone(), *wall_rule)
+        }
+    };
+    active_map.0 = map;
+}
+
+/// Re-enters [`AppState::Playing`] with a fresh board once the level's food target is reached.
+fn advance_level(
+    event: On<FoodEaten>,
+    mut progress: ResMut<LevelProgress>,
+    mut level: ResMut<CurrentLevel>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // Poison doesn't count.
+    if event.kind.food_type().growth <= 0 {
+        return;
+    }
+
+    progress.0 += 1;
+    let Some(target) = level.get().food_target else {
+        return;
+    };
+
+    if progress.0 >= target && level.0 + 1 < LEVELS.len() {
+        level.0 += 1;
+        info!("Level {} reached!", level.number());
+        next_state.set(AppState::Playing);
+    }
+}
@@ -0,0 +1,86 @@
+//! Text files read line by line, used for replays and saved games. The first
+//! line names the kind of file and the version of its format, e.g.
+//! `snake-replay 1`, and files from other versions are refused.
+
+use std::fmt;
+
+/// The lines after the `snake-<kind> <version>` header, trimmed and numbered
+/// from 1, without the blank ones.
+pub fn lines<'a>(
+    source: &'a str,
+    kind: &str,
+    version: u32,
+) -> Result<impl Iterator<Item = (usize, &'a str)>, LineError> {
+    let mut lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let prefix = format!("snake-{kind} ");
+    match lines.next() {
+        Some((_, header)) if header.strip_prefix(&prefix) == Some(&version.to_string()) => {}
+        Some((line, header)) if header.starts_with(&prefix) => {
+            return Err(LineError::new(
+                line,
+                format!("`{header}` is from another version, expected {version}"),
+            ));
+        }
+        Some((line, _)) => return Err(LineError::new(line, format!("not a Snake {kind}"))),
+        None => return Err(LineError::new(1, format!("the {kind} is empty"))),
+    }
+    Ok(lines)
+}
+
+/// A malformed file, with a 1-based line in it.
+#[derive(Debug)]
+pub struct LineError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl LineError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for LineError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> LineError {
+        lines(source, "save", 2).err().unwrap()
+    }
+
+    #[test]
+    fn skips_the_header_and_blank_lines() {
+        let lines: Vec<_> = lines("snake-save 2\nseed 1\n\n  score 3 \n", "save", 2)
+            .unwrap()
+            .collect();
+
+        assert_eq!(lines, vec![(2, "seed 1"), (4, "score 3")]);
+    }
+
+    #[test]
+    fn refuses_other_versions_and_kinds() {
+        assert_eq!(
+            error("snake-save 1\nseed 1\n").message,
+            "`snake-save 1` is from another version, expected 2"
+        );
+        assert_eq!(error("snake-save 20\n").line, 1);
+        assert_eq!(error("\nsnake-replay 2\n").line, 2);
+        assert_eq!(error("").message, "the save is empty");
+    }
+}
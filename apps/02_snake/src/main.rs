@@ -3,11 +3,25 @@
 // Disable console on Windows for non-dev builds.
 #![cfg_attr(not(feature = "dev"), windows_subsystem = "windows")]
 
+mod autopilot;
+mod board;
 mod food;
 mod grid;
+mod level;
+mod line_file;
+mod map;
+mod pause;
 mod player;
+mod replay;
+mod save;
+mod score;
+mod seed;
 mod ui;
 
+use crate::autopilot::AttractMode;
+use crate::board::DeathReason;
+use crate::map::MapAssets;
+use crate::player::Player;
 use bevy::asset::AssetMetaCheck;
 use bevy::prelude::*;
 use bevy::window::{WindowMode, WindowResolution};
@@ -55,15 +69,29 @@ impl Plugin for AppPlugin {
         app.insert_resource(ClearColor(Color::srgb_u8(82, 133, 57)));
         app.init_state::<AppState>();
         app.init_state::<AssetState>();
-        app.init_resource::<MenuTimer>();
+        app.add_sub_state::<PlayState>();
 
-        app.add_plugins((ui::plugin, grid::plugin, player::plugin, food::plugin));
+        app.add_plugins((
+            ui::plugin,
+            grid::plugin,
+            player::plugin,
+            food::plugin,
+            score::plugin,
+            level::plugin,
+            map::plugin,
+            autopilot::plugin,
+            seed::plugin,
+            replay::plugin,
+            pause::plugin,
+            save::plugin,
+        ));
 
         app.add_loading_state(
             LoadingState::new(AssetState::Loading)
                 .continue_to_state(AssetState::Done)
                 .on_failure_continue_to_state(AssetState::Error)
-                .load_collection::<GlobalAssets>(),
+                .load_collection::<GlobalAssets>()
+                .load_collection::<MapAssets>(),
         );
 
         app.configure_sets(
@@ -75,14 +103,16 @@ impl Plugin for AppPlugin {
             )
                 .chain(),
         );
+        app.configure_sets(
+            Update,
+            PausableSystems.run_if(not(in_state(PlayState::Paused))),
+        );
 
         app.add_systems(Startup, setup_camera);
         app.add_systems(OnEnter(AssetState::Done), enter_menu);
         app.add_systems(
-            Update,
-            tick_menu_timer
-                .run_if(in_state(AppState::Menu))
-                .in_set(AppSystems::TickTimers),
+            OnEnter(AppState::GameOver),
+            play_game_over_sound.run_if(not(resource_equals(AttractMode(true)))),
         );
     }
 }
@@ -100,12 +130,44 @@ enum AppSystems {
     Render,
 }
 
+/// Systems that stop while the game is paused, see [`PlayState`].
+#[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+struct PausableSystems;
+
 #[derive(States, Debug, Default, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 enum AppState {
     #[default]
     Loading,
     Menu,
     Playing,
+    /// The snake filled the whole board.
+    Won,
+    /// The snake crashed, see [`GameOverInfo`] for how.
+    GameOver,
+}
+
+/// Whether the game being played is running. Every level starts running.
+#[derive(SubStates, Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[source(AppState = AppState::Playing)]
+enum PlayState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// How the last game ended, shown on the game over screen.
+#[derive(Resource, Debug)]
+enum GameOverInfo {
+    Solo {
+        reason: DeathReason,
+        length: usize,
+        score: u32,
+    },
+    /// The player left standing, `None` for a draw, and why the other snake crashed.
+    Versus {
+        winner: Option<Player>,
+        reason: DeathReason,
+    },
 }
 
 #[derive(States, Debug, Default, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
@@ -124,32 +186,17 @@ struct GlobalAssets {
     game_over_sound: Handle<AudioSource>,
 }
 
-#[derive(Resource)]
-struct MenuTimer(Timer);
-
-impl Default for MenuTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(1.0, TimerMode::Repeating))
-    }
-}
-
 fn setup_camera(mut commands: Commands) {
     commands.spawn((Camera2d,));
 }
 
-fn enter_menu(mut next_state: ResMut<NextState<AppState>>, mut timer: ResMut<MenuTimer>) {
-    timer.0.reset();
+fn enter_menu(mut next_state: ResMut<NextState<AppState>>) {
     next_state.set(AppState::Menu);
 }
 
-fn tick_menu_timer(
-    time: Res<Time>,
-    mut menu_timer: ResMut<MenuTimer>,
-    mut next_state: ResMut<NextState<AppState>>,
-) {
-    menu_timer.0.tick(time.delta());
-
-    if menu_timer.0.just_finished() {
-        next_state.set(AppState::Playing);
-    }
+fn play_game_over_sound(mut commands: Commands, global_assets: Res<GlobalAssets>) {
+    commands.spawn((
+        AudioPlayer::new(global_assets.game_over_sound.clone()),
+        PlaybackSettings::ONCE,
+    ));
 }
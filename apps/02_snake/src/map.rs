@@ -0,0 +1,531 @@
+//! Plain-text board maps.
+//!
+//! The first line gives the snake's starting heading, e.g. `heading right`.
+//! Every following line is one row of the board, top row first: `#` is a wall,
+//! `o` an obstacle, `.` floor and `S` the cell the snake's head starts on. An
+//! optional `T` is where the second snake starts in versus games, heading the
+//! other way. The rest of each snake is laid out behind its head, so those
+//! cells must be floor. A digit from `1` to `9` is a portal, linked to the one
+//! other cell with the same digit.
+
+use crate::board::Direction;
+use crate::grid::{GridPos, GridSize};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoadFailedEvent, AssetLoader, LoadContext};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::AssetCollection;
+use std::fmt;
+
+/// Length of a freshly spawned snake, head included.
+pub const START_LENGTH: usize = 3;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<LevelMap>();
+    app.init_asset_loader::<LevelMapLoader>();
+    app.init_resource::<ActiveMap>();
+    app.init_resource::<AssetErrors>();
+    app.add_systems(Update, record_map_errors);
+}
+
+/// Every map used by [`crate::level::LEVELS`], keyed by path.
+#[derive(AssetCollection, Resource)]
+pub struct MapAssets {
+    #[asset(
+        paths(
+            "maps/level1.map",
+            "maps/level2.map",
+            "maps/level3.map",
+            "maps/level4.map"
+        ),
+        collection(typed, mapped)
+    )]
+    pub maps: HashMap<String, Handle<LevelMap>>,
+}
+
+/// Map of the level being played.
+#[derive(Resource, Default)]
+pub struct ActiveMap(pub LevelMap);
+
+/// Descriptions of assets that failed to load, shown on the error screen.
+#[derive(Resource, Default)]
+pub struct AssetErrors(pub Vec<String>);
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MapCell {
+    Floor,
+    Wall,
+    Obstacle,
+    /// Sends a head that enters it out of the linked portal, see
+    /// [`LevelMap::linked_portal`]. Food is never put on one.
+    Portal,
+}
+
+impl MapCell {
+    /// Whether a snake can be on the cell.
+    pub fn is_passable(self) -> bool {
+        matches!(self, MapCell::Floor | MapCell::Portal)
+    }
+}
+
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct LevelMap {
+    pub size: GridSize,
+    /// Row-major, starting from the bottom row like [`GridPos`].
+    cells: Vec<MapCell>,
+    pub spawn: GridPos,
+    pub heading: Direction,
+    /// The second snake's head, which starts heading the opposite way.
+    pub second_spawn: Option<GridPos>,
+    /// Linked portal cells.
+    pub portals: Vec<[GridPos; 2]>,
+}
+
+/// An open 11×9 board, the snake starting on the left heading right and the
+/// second one on the right heading left, two rows up.
+impl Default for LevelMap {
+    fn default() -> Self {
+        let size = GridSize::default();
+        Self {
+            size,
+            cells: vec![MapCell::Floor; (size.width * size.height) as usize],
+            spawn: GridPos { x: 3, y: 4 },
+            heading: Direction::Right,
+            second_spawn: Some(GridPos { x: 7, y: 6 }),
+            portals: Vec::new(),
+        }
+    }
+}
+
+impl LevelMap {
+    pub fn cell(&self, pos: GridPos) -> MapCell {
+        if self.size.contains(pos) {
+            self.cells[(pos.y * self.size.width + pos.x) as usize]
+        } else {
+            MapCell::Wall
+        }
+    }
+
+    /// The other end of the portal on `pos`, if there is one.
+    pub fn linked_portal(&self, pos: GridPos) -> Option<GridPos> {
+        self.portals.iter().find_map(|&[first, second]| {
+            if pos == first {
+                Some(second)
+            } else if pos == second {
+                Some(first)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Head cell and heading of each snake, first player first.
+    pub fn spawns(&self) -> impl Iterator<Item = (GridPos, Direction)> {
+        let first = (self.spawn, self.heading);
+        let second = self
+            .second_spawn
+            .map(|spawn| (spawn, self.heading.opposite()));
+        std::iter::once(first).chain(second)
+    }
+
+    /// Starting cells of a snake, head first.
+    pub fn segments_behind(head: GridPos, heading: Direction) -> impl Iterator<Item = GridPos> {
+        let delta = heading.delta();
+        (0..START_LENGTH as i32).map(move |i| GridPos {
+            x: head.x - delta.x * i,
+            y: head.y - delta.y * i,
+        })
+    }
+
+    /// The map centred on a board of at least `size`, the extra cells being floor.
+    pub fn fitted(&self, size: GridSize) -> Self {
+        let size = GridSize {
+            width: size.width.max(self.size.width),
+            height: size.height.max(self.size.height),
+        };
+        let offset = |pos: GridPos| GridPos {
+            x: pos.x + (size.width - self.size.width) / 2,
+            y: pos.y + (size.height - self.size.height) / 2,
+        };
+
+        let mut cells = vec![MapCell::Floor; (size.width * size.height) as usize];
+        for pos in self.size.cells() {
+            let target = offset(pos);
+            cells[(target.y * size.width + target.x) as usize] = self.cell(pos);
+        }
+
+        Self {
+            size,
+            cells,
+            spawn: offset(self.spawn),
+            heading: self.heading,
+            second_spawn: self.second_spawn.map(offset),
+            portals: self
+                .portals
+                .iter()
+                .map(|portals| portals.map(offset))
+                .collect(),
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Self, MapError> {
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line));
+
+        let Some((_, header)) = lines.next() else {
+            return Err(MapError::new(1, 1, "the map is empty"));
+        };
+        let heading = match header.trim().strip_prefix("heading ") {
+            Some("up") => Direction::Up,
+            Some("down") => Direction::Down,
+            Some("left") => Direction::Left,
+            Some("right") => Direction::Right,
+            _ => {
+                return Err(MapError::new(
+                    1,
+                    1,
+                    "expected `heading up`, `heading down`, `heading left` or `heading right`",
+                ));
+            }
+        };
+
+        let mut rows = Vec::new();
+        let mut spawn = None;
+        let mut second_spawn = None;
+        // Where each digit was found, in the order of the file.
+        let mut portal_ends: Vec<(char, Vec<(usize, usize, i32, i32)>)> = Vec::new();
+        for (line, text) in lines {
+            let text = text.trim_end();
+            if text.is_empty() {
+                continue;
+            }
+
+            let mut row = Vec::new();
+            for (index, char) in text.chars().enumerate() {
+                let column = index + 1;
+                row.push(match char {
+                    '.' => MapCell::Floor,
+                    '#' => MapCell::Wall,
+                    'o' => MapCell::Obstacle,
+                    'S' => {
+                        if spawn.is_some() {
+                            return Err(MapError::new(line, column, "a second spawn `S`"));
+                        }
+                        spawn = Some((line, column, rows.len() as i32, index as i32));
+                        MapCell::Floor
+                    }
+                    'T' => {
+                        if second_spawn.is_some() {
+                            return Err(MapError::new(line, column, "a second `T`"));
+                        }
+                        second_spawn = Some((line, column, rows.len() as i32, index as i32));
+                        MapCell::Floor
+                    }
+                    '1'..='9' => {
+                        let end = (line, column, rows.len() as i32, index as i32);
+                        match portal_ends.iter_mut().find(|(digit, _)| *digit == char) {
+                            Some((_, ends)) if ends.len() == 2 => {
+                                return Err(MapError::new(
+                                    line,
+                                    column,
+                                    format!("a third portal `{char}`"),
+                                ));
+                            }
+                            Some((_, ends)) => ends.push(end),
+                            None => portal_ends.push((char, vec![end])),
+                        }
+                        MapCell::Portal
+                    }
+                    _ => {
+                        return Err(MapError::new(
+                            line,
+                            column,
+                            format!(
+                                "unexpected `{char}`, expected `#`, `o`, `.`, `S`, `T` or a portal digit"
+                            ),
+                        ));
+                    }
+                });
+            }
+
+            if let Some(expected) = rows.first().map(Vec::len)
+                && row.len() != expected
+            {
+                return Err(MapError::new(
+                    line,
+                    row.len().min(expected) + 1,
+                    format!("row is {} cells wide, expected {expected}", row.len()),
+                ));
+            }
+            rows.push(row);
+        }
+
+        let last_line = source.lines().count().max(1);
+        if rows.is_empty() {
+            return Err(MapError::new(last_line, 1, "the map has no rows"));
+        }
+        let Some((spawn_line, spawn_column, spawn_row, spawn_x)) = spawn else {
+            return Err(MapError::new(last_line, 1, "the map has no spawn `S`"));
+        };
+        if let Some((digit, ends)) = portal_ends.iter().find(|(_, ends)| ends.len() < 2) {
+            let (line, column, _, _) = ends[0];
+            return Err(MapError::new(
+                line,
+                column,
+                format!("portal `{digit}` has no other end"),
+            ));
+        }
+
+        let size = GridSize {
+            width: rows[0].len() as i32,
+            height: rows.len() as i32,
+        };
+        // The first row in the file is the top of the board.
+        let to_grid = |row: i32, x: i32| GridPos {
+            x,
+            y: size.height - 1 - row,
+        };
+        let cells = rows.into_iter().rev().flatten().collect();
+        let map = LevelMap {
+            size,
+            cells,
+            spawn: to_grid(spawn_row, spawn_x),
+            heading,
+            second_spawn: second_spawn.map(|(_, _, row, x)| to_grid(row, x)),
+            portals: portal_ends
+                .iter()
+                .map(|(_, ends)| {
+                    [0, 1].map(|end| {
+                        let (_, _, row, x) = ends[end];
+                        to_grid(row, x)
+                    })
+                })
+                .collect(),
+        };
+
+        let mut taken = Vec::new();
+        let starts = std::iter::once((spawn_line, spawn_column))
+            .chain(second_spawn.map(|(line, column, _, _)| (line, column)));
+        for ((line, column), (head, heading)) in starts.zip(map.spawns()) {
+            for pos in LevelMap::segments_behind(head, heading) {
+                if map.cell(pos) != MapCell::Floor || taken.contains(&pos) {
+                    return Err(MapError::new(
+                        line,
+                        column,
+                        format!("the {START_LENGTH} cells behind the spawn must be free floor"),
+                    ));
+                }
+                taken.push(pos);
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// A malformed map, with a 1-based position in the file.
+#[derive(Debug)]
+pub struct MapError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl MapError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for MapError {}
+
+#[derive(Debug)]
+pub enum LevelMapLoaderError {
+    Io(std::io::Error),
+    Utf8(std::string::FromUtf8Error),
+    Parse(MapError),
+}
+
+impl fmt::Display for LevelMapLoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelMapLoaderError::Io(error) => write!(f, "could not read the map: {error}"),
+            LevelMapLoaderError::Utf8(error) => write!(f, "the map is not UTF-8: {error}"),
+            LevelMapLoaderError::Parse(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LevelMapLoaderError {}
+
+#[derive(Default, TypePath)]
+struct LevelMapLoader;
+
+impl AssetLoader for LevelMapLoader {
+    type Asset = LevelMap;
+    type Settings = ();
+    type Error = LevelMapLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<LevelMap, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(LevelMapLoaderError::Io)?;
+        let source = String::from_utf8(bytes).map_err(LevelMapLoaderError::Utf8)?;
+        LevelMap::parse(&source).map_err(LevelMapLoaderError::Parse)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["map"]
+    }
+}
+
+fn record_map_errors(
+    mut failures: MessageReader<AssetLoadFailedEvent<LevelMap>>,
+    mut errors: ResMut<AssetErrors>,
+) {
+    for failure in failures.read() {
+        error!("Failed to load map {}: {}", failure.path, failure.error);
+        errors
+            .0
+            .push(format!("{}: {}", failure.path, failure.error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_walls_obstacles_and_spawn() {
+        let map =
+            LevelMap::parse("heading up\n#####\n#.o.#\n#.S.#\n#...#\n#...#\n#####\n").unwrap();
+
+        assert_eq!(
+            map.size,
+            GridSize {
+                width: 5,
+                height: 6
+            }
+        );
+        assert_eq!(map.spawn, GridPos { x: 2, y: 3 });
+        assert_eq!(map.heading, Direction::Up);
+        assert_eq!(map.cell(GridPos { x: 0, y: 0 }), MapCell::Wall);
+        assert_eq!(map.cell(GridPos { x: 2, y: 4 }), MapCell::Obstacle);
+        assert_eq!(map.cell(GridPos { x: 2, y: 1 }), MapCell::Floor);
+    }
+
+    #[test]
+    fn reports_the_position_of_an_unknown_character() {
+        let error = LevelMap::parse("heading right\n.....\n..x..\n.S...\n").unwrap_err();
+
+        assert_eq!((error.line, error.column), (3, 3));
+    }
+
+    #[test]
+    fn reports_a_ragged_row() {
+        let error = LevelMap::parse("heading right\n.....\n...\n..S..\n").unwrap_err();
+
+        assert_eq!((error.line, error.column), (3, 4));
+    }
+
+    #[test]
+    fn parses_the_second_spawn() {
+        let map = LevelMap::parse("heading right\n..T....\n....S..\n").unwrap();
+
+        assert_eq!(map.second_spawn, Some(GridPos { x: 2, y: 1 }));
+        assert_eq!(
+            map.spawns().nth(1),
+            Some((GridPos { x: 2, y: 1 }, Direction::Left))
+        );
+    }
+
+    #[test]
+    fn fitting_centres_the_map() {
+        let map = LevelMap::parse("heading right\n....o\n..S..\n").unwrap();
+        let fitted = map.fitted(GridSize {
+            width: 9,
+            height: 3,
+        });
+
+        assert_eq!(
+            fitted.size,
+            GridSize {
+                width: 9,
+                height: 3
+            }
+        );
+        assert_eq!(fitted.spawn, GridPos { x: 4, y: 0 });
+        assert_eq!(fitted.cell(GridPos { x: 6, y: 1 }), MapCell::Obstacle);
+        assert_eq!(fitted.cell(GridPos { x: 8, y: 2 }), MapCell::Floor);
+        assert_eq!(
+            map.fitted(GridSize {
+                width: 1,
+                height: 1
+            })
+            .size,
+            map.size
+        );
+    }
+
+    #[test]
+    fn links_portals_with_the_same_digit() {
+        let map = LevelMap::parse("heading right\n1..2.\n..S..\n2...1\n").unwrap();
+
+        assert_eq!(map.cell(GridPos { x: 0, y: 2 }), MapCell::Portal);
+        assert_eq!(
+            map.linked_portal(GridPos { x: 4, y: 0 }),
+            Some(GridPos { x: 0, y: 2 })
+        );
+        assert_eq!(
+            map.linked_portal(GridPos { x: 3, y: 2 }),
+            Some(GridPos { x: 0, y: 0 })
+        );
+        assert_eq!(map.linked_portal(GridPos { x: 1, y: 1 }), None);
+    }
+
+    #[test]
+    fn rejects_unpaired_portals() {
+        let error = LevelMap::parse("heading right\n..1..\n..S..\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+
+        let error = LevelMap::parse("heading right\n1.1.1\n..S..\n").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+    }
+
+    #[test]
+    fn rejects_a_second_snake_overlapping_the_first() {
+        let error = LevelMap::parse("heading right\n.......\n..T.S..\n").unwrap_err();
+
+        assert_eq!((error.line, error.column), (3, 3));
+    }
+
+    #[test]
+    fn rejects_a_snake_that_does_not_fit_behind_the_spawn() {
+        let error = LevelMap::parse("heading right\n.....\n.S...\n.....\n").unwrap_err();
+
+        assert_eq!((error.line, error.column), (3, 2));
+    }
+}
@@ -0,0 +1,152 @@
+//! Pausing a game: the snakes and food timers stop until it is resumed,
+//! restarted or left for the menu.
+
+use crate::autopilot::AttractMode;
+use crate::replay::Playback;
+use crate::save::SaveGame;
+use crate::{AppState, PlayState};
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use bevy_enhanced_input::actions;
+use bevy_enhanced_input::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_input_context::<PauseControls>();
+    app.add_input_context::<PauseMenu>();
+    app.add_observer(toggle_pause);
+    app.add_observer(restart);
+    app.add_observer(quit_to_menu);
+
+    app.add_systems(
+        OnEnter(AppState::Playing),
+        spawn_pause_controls.run_if(can_pause),
+    );
+    app.add_systems(OnEnter(PlayState::Paused), spawn_pause_menu);
+    app.add_systems(
+        OnEnter(AppState::Menu),
+        skip_menu.run_if(resource_exists::<RestartRequested>),
+    );
+    app.add_systems(OnExit(AppState::Menu), clear_restart);
+    app.add_systems(
+        Update,
+        pause_on_focus_loss.run_if(in_state(PlayState::Running).and(can_pause)),
+    );
+}
+
+/// Holds the key that pauses and resumes a game.
+#[derive(Component)]
+struct PauseControls;
+
+#[derive(Component)]
+struct PauseMenu;
+
+/// "Restart" goes through the menu, so a new run starts the same way as from
+/// there, but the menu isn't shown.
+#[derive(Resource)]
+pub struct RestartRequested;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct PauseAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct RestartAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct QuitToMenuAction;
+
+/// Demos stop on any key and replays have their own controls.
+fn can_pause(attract_mode: Res<AttractMode>, playback: Option<Res<Playback>>) -> bool {
+    !attract_mode.0 && playback.is_none()
+}
+
+fn spawn_pause_controls(mut commands: Commands) {
+    commands.spawn((
+        PauseControls,
+        DespawnOnExit(AppState::Playing),
+        actions!(
+            PauseControls[(
+                Action::<PauseAction>::new(),
+                bindings![KeyCode::Escape, KeyCode::KeyP]
+            )]
+        ),
+    ));
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands.spawn((
+        PauseMenu,
+        Node {
+            position_type: PositionType::Absolute,
+            width: vw(100),
+            height: vh(100),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: px(10),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+        DespawnOnExit(PlayState::Paused),
+        actions!(
+            PauseMenu[
+                (Action::<RestartAction>::new(), bindings![KeyCode::KeyR]),
+                (Action::<QuitToMenuAction>::new(), bindings![KeyCode::KeyQ]),
+            ]
+        ),
+        children![
+            (Text::new("Paused"), TextFont::from_font_size(64.0)),
+            Text::new("Press 'esc' to resume, 'r' to restart, 'q' to save and quit to the menu"),
+        ],
+    ));
+}
+
+fn toggle_pause(
+    _event: On<Start<PauseAction>>,
+    state: Res<State<PlayState>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    next_state.set(match state.get() {
+        PlayState::Running => PlayState::Paused,
+        PlayState::Paused => PlayState::Running,
+    });
+}
+
+fn restart(
+    _event: On<Start<RestartAction>>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    commands.insert_resource(RestartRequested);
+    next_state.set(AppState::Menu);
+}
+
+/// Saves the game first, so it can be continued from the menu.
+fn quit_to_menu(
+    _event: On<Start<QuitToMenuAction>>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    commands.trigger(SaveGame);
+    next_state.set(AppState::Menu);
+}
+
+fn skip_menu(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Playing);
+}
+
+fn clear_restart(mut commands: Commands) {
+    commands.remove_resource::<RestartRequested>();
+}
+
+fn pause_on_focus_loss(
+    mut focus_events: MessageReader<WindowFocused>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    if focus_events.read().any(|event| !event.focused) {
+        info!("Window lost focus -> Paused");
+        next_state.set(PlayState::Paused);
+    }
+}
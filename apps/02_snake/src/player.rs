@@ -1,52 +1,95 @@
-use crate::food::{Food, FoodEaten};
-use crate::grid::{grid_to_world, GridPos, GRID_HEIGHT, GRID_WIDTH, TILE_SIZE};
-use crate::{AppState, AppSystems, AssetState, GlobalAssets};
-use bevy::asset::Assets;
+use crate::board::{DeathReason, Direction, SegmentShape, SnakeBoard, StepOutcome};
+use crate::food::{Food, FoodEaten, FoodKind};
+use crate::grid::{GridPos, GridSize, PreviousGridPos, TILE_SIZE};
+use crate::level::{CurrentLevel, load_level_map};
+use crate::map::START_LENGTH;
+use crate::replay::Playback;
+use crate::score::{Score, food_points};
+use crate::{
+    AppState, AppSystems, AssetState, GameOverInfo, GlobalAssets, PausableSystems, PlayState,
+};
+use bevy::asset::{Assets, RenderAssetUsages};
 use bevy::color::palettes::css::{BLACK, WHITE};
-use bevy::color::palettes::tailwind::{BLUE_400, BLUE_500};
-use bevy::mesh::Mesh;
+use bevy::color::palettes::tailwind::{BLUE_400, BLUE_500, ORANGE_400, ORANGE_500};
+use bevy::mesh::{Indices, Mesh, PrimitiveTopology};
 use bevy::prelude::*;
 use bevy_enhanced_input::actions;
 use bevy_enhanced_input::prelude::*;
+use std::f32::consts::{PI, TAU};
+use std::time::Duration;
 
-const HEAD_COLOR: Color = Color::Srgba(BLUE_500);
-const BODY_COLOR: Color = Color::Srgba(BLUE_400);
+/// Indexed by player.
+const HEAD_COLORS: [Color; 2] = [Color::Srgba(BLUE_500), Color::Srgba(ORANGE_500)];
+const BODY_COLORS: [Color; 2] = [Color::Srgba(BLUE_400), Color::Srgba(ORANGE_400)];
 const SNAKE_Z: f32 = 2.0;
+/// Below the head, which slides over the neck.
+const BODY_Z: f32 = 1.9;
+/// Width of the body, as a fraction of a tile.
+const BODY_WIDTH: f32 = 0.8;
 const SNAKE_MOVE_INTERVAL: f32 = 0.24;
+/// Move interval multiplier while a speed food is active.
+const SPEED_BOOST_FACTOR: f32 = 0.6;
+const SPEED_BOOST_DURATION: f32 = 5.0;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_resource::<MoveTimer>();
-    app.init_resource::<CurrentDirection>();
-    app.init_resource::<SnakeSegments>();
-    app.init_resource::<PendingGrowth>();
+    app.init_resource::<SpeedBoost>();
+    app.init_resource::<Board>();
+    app.init_resource::<GameMode>();
+    app.init_resource::<StepCount>();
 
     app.add_input_context::<SnakeHead>();
     app.add_observer(observe_up);
     app.add_observer(observe_down);
     app.add_observer(observe_right);
     app.add_observer(observe_left);
+    app.add_observer(apply_food_effect);
 
     app.add_systems(OnEnter(AssetState::Loading), setup);
+    app.add_systems(OnEnter(PlayState::Paused), disable_controls);
+    app.add_systems(OnExit(PlayState::Paused), enable_controls);
+    app.add_systems(
+        OnTransition {
+            exited: AppState::Menu,
+            entered: AppState::Playing,
+        },
+        reset_step_count,
+    );
     app.add_systems(
         OnEnter(AppState::Playing),
-        (reset_resources, spawn_player).chain(),
+        (reset_resources, spawn_player)
+            .chain()
+            .after(load_level_map),
     );
     app.add_systems(
         Update,
-        tick_move_timer
+        (tick_move_timer, tick_speed_boost)
             .run_if(in_state(AppState::Playing))
-            .in_set(AppSystems::TickTimers),
+            .in_set(AppSystems::TickTimers)
+            .in_set(PausableSystems),
     );
     app.add_systems(
         Update,
-        (snake_movement, check_snake_collisions)
-            .chain()
+        move_snake
+            .run_if(in_state(AppState::Playing))
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        apply_speed_curve
             .run_if(in_state(AppState::Playing))
-            .in_set(AppSystems::Update),
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
     );
     app.add_systems(
         Update,
-        sync_head_rotation_to_transform
+        (
+            shape_segments,
+            interpolate_segments,
+            sync_head_rotation_to_transform,
+        )
+            .chain()
             .run_if(in_state(AppState::Playing))
             .in_set(AppSystems::Render),
     );
@@ -55,14 +98,87 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Component)]
 struct SnakeSegment;
 
-#[derive(Resource, Default, Clone, Eq, PartialEq)]
-pub struct PendingGrowth(pub u32);
+/// Child of a body segment that fills the cell where the body turns a corner,
+/// so it doesn't look cut while the segments slide between cells.
+#[derive(Component)]
+struct SegmentJoint;
+
+/// The game being played. Systems in this module mirror it onto the snake entities.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Board(pub SnakeBoard);
+
+/// Board steps since the run started from the menu, across levels.
+#[derive(Resource, Default)]
+pub struct StepCount(pub u64);
+
+/// Solo or versus, chosen in the menu.
+#[derive(Resource, Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GameMode {
+    #[default]
+    Solo,
+    /// Two snakes on the same board, the round ends when one dies.
+    Versus,
+}
+
+impl GameMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            GameMode::Solo => GameMode::Versus,
+            GameMode::Versus => GameMode::Solo,
+        }
+    }
 
-#[derive(Resource, Default, Clone, Eq, PartialEq)]
+    pub fn players(self) -> usize {
+        match self {
+            GameMode::Solo => 1,
+            GameMode::Versus => 2,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Solo => "solo",
+            GameMode::Versus => "versus",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        [GameMode::Solo, GameMode::Versus]
+            .into_iter()
+            .find(|mode| mode.label() == label)
+    }
+}
+
+/// The snake a head belongs to, an index into [`SnakeBoard::snakes`].
+#[derive(Component, Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Player(pub usize);
+
+impl Player {
+    pub fn name(self) -> String {
+        format!("Player {}", self.0 + 1)
+    }
+}
+
+/// Snake entities in the same order as [`crate::board::Snake::body`], head
+/// first. Lives on the head.
+#[derive(Component, Default, Clone, Eq, PartialEq)]
 pub struct SnakeSegments(pub Vec<Entity>);
 
+/// Rotations in radians, turned from `previous_angle` to `angle` during a step.
 #[derive(Component)]
-struct SnakeHead(f32); // rotation in radians
+struct SnakeHead {
+    angle: f32,
+    previous_angle: f32,
+}
+
+impl SnakeHead {
+    fn facing(direction: Direction) -> Self {
+        Self {
+            angle: direction.angle(),
+            previous_angle: direction.angle(),
+        }
+    }
+}
 
 #[derive(InputAction)]
 #[action_output(bool)]
@@ -80,11 +196,9 @@ struct MoveRight;
 #[action_output(bool)]
 struct MoveLeft;
 
-#[derive(Resource, Default, Clone, Eq, PartialEq)]
-struct CurrentDirection(Direction);
-
+/// Finishes once per board step.
 #[derive(Resource)]
-struct MoveTimer(Timer);
+pub struct MoveTimer(pub Timer);
 
 impl Default for MoveTimer {
     fn default() -> Self {
@@ -95,47 +209,31 @@ impl Default for MoveTimer {
     }
 }
 
+/// Time left on the last speed food, see [`SPEED_BOOST_FACTOR`].
 #[derive(Resource)]
-pub struct SnakeAssets {
-    mesh: Handle<Mesh>,
-    body_material: Handle<ColorMaterial>,
-}
+struct SpeedBoost(Timer);
 
-#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
-enum Direction {
-    #[default]
-    Right,
-    Left,
-    Up,
-    Down,
+impl Default for SpeedBoost {
+    fn default() -> Self {
+        // Starts out finished, so there is no boost until a speed food is eaten.
+        let mut timer = Timer::from_seconds(SPEED_BOOST_DURATION, TimerMode::Once);
+        timer.tick(timer.duration());
+        Self(timer)
+    }
 }
 
-impl Direction {
-    fn delta(self) -> IVec2 {
-        match self {
-            Direction::Up => IVec2::new(0, 1),
-            Direction::Down => IVec2::new(0, -1),
-            Direction::Left => IVec2::new(-1, 0),
-            Direction::Right => IVec2::new(1, 0),
-        }
-    }
-    fn angle(self) -> f32 {
-        match self {
-            Direction::Up => std::f32::consts::FRAC_PI_2,    // 90°
-            Direction::Down => -std::f32::consts::FRAC_PI_2, // -90°
-            Direction::Left => std::f32::consts::PI,         // 180°
-            Direction::Right => 0.0,                         // 0°
-        }
-    }
-    fn is_opposite(self, other: Direction) -> bool {
-        matches!(
-            (self, other),
-            (Direction::Up, Direction::Down)
-                | (Direction::Down, Direction::Up)
-                | (Direction::Left, Direction::Right)
-                | (Direction::Right, Direction::Left)
-        )
-    }
+/// Body meshes point right, the corner joins the right and top sides. They are
+/// rotated to fit, see [`shape_segments`].
+#[derive(Resource)]
+pub struct SnakeAssets {
+    mesh: Handle<Mesh>,
+    straight_mesh: Handle<Mesh>,
+    corner_mesh: Handle<Mesh>,
+    tail_mesh: Handle<Mesh>,
+    joint_mesh: Handle<Mesh>,
+    /// Indexed by player.
+    head_materials: Vec<Handle<ColorMaterial>>,
+    body_materials: Vec<Handle<ColorMaterial>>,
 }
 
 fn setup(
@@ -143,26 +241,85 @@ fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let mesh = meshes.add(Rectangle::new(TILE_SIZE * 0.9, TILE_SIZE * 0.8));
-    let body_material = materials.add(ColorMaterial::from_color(BODY_COLOR));
+    let half_width = TILE_SIZE * BODY_WIDTH / 2.0;
+    let edge = TILE_SIZE / 2.0;
+
+    let mesh = meshes.add(Rectangle::new(TILE_SIZE * 0.9, TILE_SIZE * BODY_WIDTH));
+    let straight_mesh = meshes.add(Rectangle::new(TILE_SIZE, TILE_SIZE * BODY_WIDTH));
+    // An L fanned out from its inner corner.
+    let corner_mesh = meshes.add(polygon_mesh(
+        &[
+            [half_width, half_width],
+            [half_width, edge],
+            [-half_width, edge],
+            [-half_width, -half_width],
+            [edge, -half_width],
+            [edge, half_width],
+        ],
+        &[0, 1, 2, 0, 2, 3, 0, 3, 4, 0, 4, 5],
+    ));
+    // Tapers from the segment ahead to a narrow tip.
+    let tail_mesh = meshes.add(polygon_mesh(
+        &[
+            [edge, half_width],
+            [-edge * 0.7, half_width * 0.4],
+            [-edge * 0.7, -half_width * 0.4],
+            [edge, -half_width],
+        ],
+        &[0, 1, 2, 0, 2, 3],
+    ));
+    let joint_mesh = meshes.add(Rectangle::new(
+        TILE_SIZE * BODY_WIDTH,
+        TILE_SIZE * BODY_WIDTH,
+    ));
+    let head_materials = HEAD_COLORS
+        .map(|color| materials.add(ColorMaterial::from_color(color)))
+        .into();
+    let body_materials = BODY_COLORS
+        .map(|color| materials.add(ColorMaterial::from_color(color)))
+        .into();
 
     commands.insert_resource(SnakeAssets {
         mesh,
-        body_material,
+        straight_mesh,
+        corner_mesh,
+        tail_mesh,
+        joint_mesh,
+        head_materials,
+        body_materials,
     });
 }
 
+/// Flat mesh from `points` in the XY plane, for shapes that aren't a primitive.
+fn polygon_mesh(points: &[[f32; 2]], indices: &[u32]) -> Mesh {
+    let positions: Vec<[f32; 3]> = points.iter().map(|&[x, y]| [x, y, 0.0]).collect();
+    let uvs: Vec<[f32; 2]> = points
+        .iter()
+        .map(|&[x, y]| [x / TILE_SIZE + 0.5, 0.5 - y / TILE_SIZE])
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; points.len()])
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices.to_vec()))
+}
+
 pub fn spawn_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     snake_assets: Res<SnakeAssets>,
-    mut segments: ResMut<SnakeSegments>,
+    board: Res<Board>,
+    size: Res<GridSize>,
+    playback: Option<Res<Playback>>,
 ) {
     let eye_x = TILE_SIZE * 0.3;
     let eye_y = TILE_SIZE * 0.2;
     let eye_radius = TILE_SIZE * 0.15;
-
     let pupil_x = eye_radius * 0.5;
     let pupil_y = eye_radius * 0.2;
     let pupil_radius = eye_radius * 0.3;
@@ -170,42 +327,22 @@ pub fn spawn_player(
     let eye_mesh = meshes.add(Circle::new(eye_radius));
     let pupil_mesh = meshes.add(Circle::new(pupil_radius));
 
-    let head_material = materials.add(ColorMaterial::from_color(HEAD_COLOR));
     let eye_material = materials.add(ColorMaterial::from_color(WHITE));
     let pupil_material = materials.add(ColorMaterial::from_color(BLACK));
 
-    let head_grid_pos = GridPos { x: 3, y: 4 };
-    let segment_grid_pos1 = GridPos { x: 2, y: 4 };
-    let segment_grid_pos2 = GridPos { x: 1, y: 4 };
+    let players = board.snakes().len();
+    for (player, snake) in board.snakes().iter().enumerate() {
+        let head_grid_pos = snake.head();
 
-    let head_segment = commands
-        .spawn((
-            SnakeHead(0.0),
+        let mut head = commands.spawn((
+            Player(player),
+            SnakeHead::facing(snake.heading()),
             head_grid_pos,
+            PreviousGridPos(head_grid_pos),
             Mesh2d(snake_assets.mesh.clone()),
-            MeshMaterial2d(head_material.clone()),
-            Transform::from_translation(grid_to_world(head_grid_pos).with_z(SNAKE_Z)),
+            MeshMaterial2d(snake_assets.head_materials[player].clone()),
+            Transform::from_translation(size.to_world(head_grid_pos).with_z(SNAKE_Z)),
             DespawnOnExit(AppState::Playing),
-            actions!(
-                SnakeHead[
-                    (
-                    Action::<MoveUp>::new(),
-                    bindings![KeyCode::KeyW, KeyCode::ArrowUp]
-                ),
-                (
-                    Action::<MoveDown>::new(),
-                    bindings![KeyCode::KeyS, KeyCode::ArrowDown]
-                ),
-                    (
-                    Action::<MoveRight>::new(),
-                    bindings![KeyCode::KeyD, KeyCode::ArrowRight]
-                ),
-                    (
-                    Action::<MoveLeft>::new(),
-                    bindings![KeyCode::KeyA, KeyCode::ArrowLeft]
-                ),
-                ]
-            ),
             children![
                 (
                     Mesh2d(eye_mesh.clone()),
@@ -228,197 +365,426 @@ pub fn spawn_player(
                     )]
                 ),
             ],
-        ))
-        .id();
+        ));
+        // Replays are steered by the recording.
+        if playback.is_none() {
+            insert_controls(&mut head, player, players);
+        }
+        let head = head.id();
+
+        let mut segments = vec![head];
+        for &pos in snake.body().iter().skip(1) {
+            segments.push(spawn_segment(
+                &mut commands,
+                &snake_assets,
+                *size,
+                player,
+                pos,
+            ));
+        }
+        commands.entity(head).insert(SnakeSegments(segments));
+    }
+}
 
-    let segment1 = commands
-        .spawn((
-            SnakeSegment,
-            segment_grid_pos1,
-            Mesh2d(snake_assets.mesh.clone()),
-            MeshMaterial2d(snake_assets.body_material.clone()),
-            Transform::from_translation(grid_to_world(segment_grid_pos1).with_z(SNAKE_Z)),
-            DespawnOnExit(AppState::Playing),
-        ))
-        .id();
+/// WASD and the arrow keys both steer a solo snake. In versus games the first
+/// player gets WASD and the second one the arrow keys.
+fn insert_controls(head: &mut EntityCommands, player: usize, players: usize) {
+    match (players, player) {
+        (1, _) => head.insert(actions!(
+            SnakeHead[
+                (
+                    Action::<MoveUp>::new(),
+                    bindings![KeyCode::KeyW, KeyCode::ArrowUp]
+                ),
+                (
+                    Action::<MoveDown>::new(),
+                    bindings![KeyCode::KeyS, KeyCode::ArrowDown]
+                ),
+                (
+                    Action::<MoveRight>::new(),
+                    bindings![KeyCode::KeyD, KeyCode::ArrowRight]
+                ),
+                (
+                    Action::<MoveLeft>::new(),
+                    bindings![KeyCode::KeyA, KeyCode::ArrowLeft]
+                ),
+            ]
+        )),
+        (_, 0) => head.insert(actions!(
+            SnakeHead[
+                (Action::<MoveUp>::new(), bindings![KeyCode::KeyW]),
+                (Action::<MoveDown>::new(), bindings![KeyCode::KeyS]),
+                (Action::<MoveRight>::new(), bindings![KeyCode::KeyD]),
+                (Action::<MoveLeft>::new(), bindings![KeyCode::KeyA]),
+            ]
+        )),
+        _ => head.insert(actions!(
+            SnakeHead[
+                (Action::<MoveUp>::new(), bindings![KeyCode::ArrowUp]),
+                (Action::<MoveDown>::new(), bindings![KeyCode::ArrowDown]),
+                (Action::<MoveRight>::new(), bindings![KeyCode::ArrowRight]),
+                (Action::<MoveLeft>::new(), bindings![KeyCode::ArrowLeft]),
+            ]
+        )),
+    };
+}
 
-    let segment2 = commands
+fn spawn_segment(
+    commands: &mut Commands,
+    snake_assets: &SnakeAssets,
+    size: GridSize,
+    player: usize,
+    pos: GridPos,
+) -> Entity {
+    commands
         .spawn((
             SnakeSegment,
-            segment_grid_pos2,
-            Mesh2d(snake_assets.mesh.clone()),
-            MeshMaterial2d(snake_assets.body_material.clone()),
-            Transform::from_translation(grid_to_world(segment_grid_pos2).with_z(SNAKE_Z)),
+            pos,
+            PreviousGridPos(pos),
+            Mesh2d(snake_assets.straight_mesh.clone()),
+            MeshMaterial2d(snake_assets.body_materials[player].clone()),
+            Transform::from_translation(size.to_world(pos).with_z(BODY_Z)),
             DespawnOnExit(AppState::Playing),
+            children![(
+                SegmentJoint,
+                Mesh2d(snake_assets.joint_mesh.clone()),
+                MeshMaterial2d(snake_assets.body_materials[player].clone()),
+                Transform::from_xyz(0.0, 0.0, -0.1),
+                Visibility::Hidden,
+            )],
         ))
-        .id();
-
-    segments.0.extend([head_segment, segment1, segment2]);
+        .id()
 }
 
 fn tick_move_timer(time: Res<Time>, mut timer: ResMut<MoveTimer>) {
     timer.0.tick(time.delta());
 }
 
-fn snake_movement(
+/// Steps the board and mirrors the result onto the snake entities.
+pub fn move_snake(
     mut commands: Commands,
     timer: Res<MoveTimer>,
-    mut segments: ResMut<SnakeSegments>,
-    mut positions: Query<&mut GridPos>,
-    current_direction: Res<CurrentDirection>,
-    mut head: Single<&mut SnakeHead>,
-    mut pending_growth: ResMut<PendingGrowth>,
+    mut board: ResMut<Board>,
+    mut snakes: Query<(&Player, &mut SnakeSegments, &mut SnakeHead)>,
+    mut positions: Query<(&mut GridPos, &mut PreviousGridPos)>,
+    food: Query<(Entity, &Food)>,
+    size: Res<GridSize>,
     snake_assets: Res<SnakeAssets>,
+    score: Res<Score>,
+    mut step_count: ResMut<StepCount>,
+    mut next_state: ResMut<NextState<AppState>>,
 ) {
     if !timer.0.just_finished() {
         return;
     }
 
-    head.0 = current_direction.0.angle();
-    let head_entity = segments.0[0];
-    let old_head = {
-        let mut head_pos = positions.get_mut(head_entity).unwrap();
-        let old = *head_pos;
-        let delta = current_direction.0.delta();
-        head_pos.x += delta.x;
-        head_pos.y += delta.y;
-        old
-    };
+    let outcomes = board.step();
+    step_count.0 += 1;
+    let solo = outcomes.len() == 1;
+    if let Some(player) = outcomes
+        .iter()
+        .position(|outcome| *outcome == StepOutcome::Ate)
+    {
+        let mut kind = FoodKind::Normal;
+        for (entity, food) in &food {
+            kind = food.0;
+            commands.entity(entity).despawn();
+        }
+        commands.trigger(FoodEaten { player, kind });
+    }
 
-    let mut previous = old_head;
+    let deaths: Vec<(usize, DeathReason)> = outcomes
+        .iter()
+        .enumerate()
+        .filter_map(|(player, outcome)| match outcome {
+            StepOutcome::Died(reason) => Some((player, *reason)),
+            _ => None,
+        })
+        .collect();
+    if let Some(&(player, reason)) = deaths.first() {
+        info!("{} -> Game over!", reason.describe());
+        let info = if solo {
+            GameOverInfo::Solo {
+                reason,
+                length: board.snake(player).length(),
+                score: score.0,
+            }
+        } else {
+            let winner = (0..outcomes.len())
+                .filter(|player| deaths.iter().all(|(dead, _)| dead != player))
+                .map(Player)
+                .next();
+            GameOverInfo::Versus { winner, reason }
+        };
+        commands.insert_resource(info);
+        next_state.set(AppState::GameOver);
+        return;
+    }
+
+    for (&player, mut segments, mut head) in &mut snakes {
+        let snake = board.snake(player.0);
+        head.previous_angle = head.angle;
+        head.angle = snake.heading().angle();
+        for (&entity, &pos) in segments.0.iter().zip(snake.body()) {
+            if let Ok((mut grid_pos, mut previous)) = positions.get_mut(entity) {
+                previous.0 = *grid_pos;
+                *grid_pos = pos;
+            }
+        }
+        let new_segments: Vec<GridPos> = snake
+            .body()
+            .iter()
+            .skip(segments.0.len())
+            .copied()
+            .collect();
+        for pos in new_segments {
+            let segment = spawn_segment(&mut commands, &snake_assets, *size, player.0, pos);
+            segments.0.push(segment);
+        }
+    }
+}
 
-    for entity in segments.0.iter().skip(1) {
-        let mut pos = positions.get_mut(*entity).unwrap();
-        previous = std::mem::replace(&mut *pos, previous);
+/// Picks each body segment's mesh and rotation from its neighbours, so the
+/// snake is drawn as one continuous body.
+fn shape_segments(
+    board: Res<Board>,
+    snake_assets: Res<SnakeAssets>,
+    snakes: Query<(&Player, &SnakeSegments)>,
+    mut segments: Query<(&mut Mesh2d, &mut Transform), With<SnakeSegment>>,
+) {
+    for (player, snake) in &snakes {
+        let shapes = board.segment_shapes(player.0);
+        for (&entity, shape) in snake.0.iter().zip(shapes) {
+            let Ok((mut mesh, mut transform)) = segments.get_mut(entity) else {
+                continue;
+            };
+            let (shape_mesh, angle) = match shape {
+                SegmentShape::Head => continue,
+                SegmentShape::Straight(ahead) => (&snake_assets.straight_mesh, ahead.angle()),
+                SegmentShape::Corner { ahead, behind } => {
+                    // The mesh joins a side to the one a quarter turn anticlockwise.
+                    let first = if (behind.angle() - ahead.angle()).rem_euclid(TAU) < PI {
+                        ahead
+                    } else {
+                        behind
+                    };
+                    (&snake_assets.corner_mesh, first.angle())
+                }
+                SegmentShape::Tail(ahead) => (&snake_assets.tail_mesh, ahead.angle()),
+            };
+            if mesh.0 != *shape_mesh {
+                mesh.0 = shape_mesh.clone();
+            }
+            transform.rotation = Quat::from_rotation_z(angle);
+        }
     }
+}
 
-    if pending_growth.0 > 0 {
-        pending_growth.0 -= 1;
-        let segment = commands
-            .spawn((
-                SnakeSegment,
-                previous,
-                Mesh2d(snake_assets.mesh.clone()),
-                MeshMaterial2d(snake_assets.body_material.clone()),
-                Transform::from_translation(grid_to_world(previous).with_z(SNAKE_Z)),
-                DespawnOnExit(AppState::Playing),
-            ))
-            .id();
-
-        segments.0.push(segment);
+/// Slides each segment from its previous cell to its current one over the
+/// step, and shows the joints where the body turns.
+fn interpolate_segments(
+    timer: Res<MoveTimer>,
+    size: Res<GridSize>,
+    board: Res<Board>,
+    snakes: Query<&SnakeSegments>,
+    mut segments: Query<
+        (
+            &GridPos,
+            &PreviousGridPos,
+            Option<&Children>,
+            &mut Transform,
+        ),
+        Without<SegmentJoint>,
+    >,
+    mut joints: Query<(&mut Transform, &mut Visibility), With<SegmentJoint>>,
+) {
+    let fraction = timer.0.fraction();
+    for snake in &snakes {
+        let mut step_ahead = None;
+        for &entity in &snake.0 {
+            let Ok((pos, previous, children, mut transform)) = segments.get_mut(entity) else {
+                continue;
+            };
+            // Asked of the board, which knows about the wrap-around edge and portals.
+            let step = board
+                .direction_between(previous.0, *pos)
+                .map_or(IVec2::ZERO, Direction::delta);
+            transform.translation = size
+                .interpolate(previous.0, *pos, step, fraction)
+                .with_z(transform.translation.z);
+
+            // The segment ahead left this segment's cell in another direction.
+            if let Some(step_ahead) = step_ahead
+                && let Some(&joint) = children.and_then(|children| children.first())
+                && let Ok((mut joint_transform, mut visibility)) = joints.get_mut(joint)
+            {
+                // The joint is a child, so its offset turns with the segment's mesh.
+                let offset = step.as_vec2() * (1.0 - fraction) * TILE_SIZE;
+                joint_transform.translation = (transform.rotation.inverse() * offset.extend(0.0))
+                    .with_z(joint_transform.translation.z);
+                visibility.set_if_neq(if step == step_ahead {
+                    Visibility::Hidden
+                } else {
+                    Visibility::Inherited
+                });
+            }
+            step_ahead = Some(step);
+        }
     }
 }
 
-fn sync_head_rotation_to_transform(head_query: Query<(&SnakeHead, &mut Transform)>) {
+/// Turns the head by the shortest way round over the step.
+fn sync_head_rotation_to_transform(
+    timer: Res<MoveTimer>,
+    head_query: Query<(&SnakeHead, &mut Transform)>,
+) {
+    let fraction = timer.0.fraction();
     for (head, mut transform) in head_query {
-        transform.rotation = Quat::from_rotation_z(head.0);
+        let turn = (head.angle - head.previous_angle + PI).rem_euclid(TAU) - PI;
+        transform.rotation = Quat::from_rotation_z(head.previous_angle + turn * fraction);
     }
 }
 
 fn observe_up(
-    _event: On<Start<MoveUp>>,
+    event: On<Start<MoveUp>>,
+    players: Query<&Player>,
     timer: ResMut<MoveTimer>,
-    current_direction: ResMut<CurrentDirection>,
+    board: ResMut<Board>,
 ) {
-    apply_movement(Direction::Up, timer, current_direction);
+    apply_movement(event.context, Direction::Up, players, timer, board);
 }
 fn observe_down(
-    _event: On<Start<MoveDown>>,
+    event: On<Start<MoveDown>>,
+    players: Query<&Player>,
     timer: ResMut<MoveTimer>,
-    current_direction: ResMut<CurrentDirection>,
+    board: ResMut<Board>,
 ) {
-    apply_movement(Direction::Down, timer, current_direction);
+    apply_movement(event.context, Direction::Down, players, timer, board);
 }
 fn observe_right(
-    _event: On<Start<MoveRight>>,
+    event: On<Start<MoveRight>>,
+    players: Query<&Player>,
     timer: ResMut<MoveTimer>,
-    current_direction: ResMut<CurrentDirection>,
+    board: ResMut<Board>,
 ) {
-    apply_movement(Direction::Right, timer, current_direction);
+    apply_movement(event.context, Direction::Right, players, timer, board);
 }
 fn observe_left(
-    _event: On<Start<MoveLeft>>,
+    event: On<Start<MoveLeft>>,
+    players: Query<&Player>,
     timer: ResMut<MoveTimer>,
-    current_direction: ResMut<CurrentDirection>,
+    board: ResMut<Board>,
 ) {
-    apply_movement(Direction::Left, timer, current_direction);
+    apply_movement(event.context, Direction::Left, players, timer, board);
 }
 
+/// Queues the turn for the snake whose keys were pressed. A solo snake moves
+/// right away, so a key press is never slower than waiting; in versus games
+/// that would hurry the other snake too.
 fn apply_movement(
+    head: Entity,
     new_direction: Direction,
+    players: Query<&Player>,
     mut timer: ResMut<MoveTimer>,
-    mut current_direction: ResMut<CurrentDirection>,
+    mut board: ResMut<Board>,
 ) {
-    if current_direction.0.is_opposite(new_direction) {
+    let Ok(&Player(player)) = players.get(head) else {
+        return;
+    };
+    if !board.turn(player, new_direction) || board.snakes().len() > 1 {
         return;
     }
 
-    current_direction.0 = new_direction;
-
     let timer_duration = timer.0.duration();
     timer.0.set_elapsed(timer_duration);
 }
 
-fn check_snake_collisions(
+/// Grows or shrinks the snake that ate, and scores the food.
+fn apply_food_effect(
+    event: On<FoodEaten>,
     mut commands: Commands,
-    segments: Res<SnakeSegments>,
-    positions: Query<&GridPos, Without<Food>>,
-    food_query: Query<(Entity, &GridPos), With<Food>>,
-    mut pending_growth: ResMut<PendingGrowth>,
-    mut next_state: ResMut<NextState<AppState>>,
+    mut board: ResMut<Board>,
+    mut snakes: Query<(&Player, &mut SnakeSegments)>,
+    timer: Res<MoveTimer>,
+    mut speed_boost: ResMut<SpeedBoost>,
+    mut score: ResMut<Score>,
     global_assets: Res<GlobalAssets>,
 ) {
-    let head_entity = segments.0[0];
-    let head_pos = positions.get(head_entity).unwrap();
-
-    // grid boundaries
-    if head_pos.x < 0
-        || head_pos.x >= GRID_WIDTH as i32
-        || head_pos.y < 0
-        || head_pos.y >= GRID_HEIGHT as i32
-    {
-        info!("You went outside of a grid -> Game over!");
-        next_state.set(AppState::Menu);
-        commands.spawn((
-            AudioPlayer::new(global_assets.game_over_sound.clone()),
-            PlaybackSettings::ONCE,
-        ));
+    let food_type = event.kind.food_type();
+    info!("You ate a {:?} food", event.kind);
+
+    if food_type.growth >= 0 {
+        board.grow(event.player, food_type.growth.unsigned_abs());
+    } else {
+        board.shrink(event.player, food_type.growth.unsigned_abs());
+        let length = board.snake(event.player).length();
+        for (player, mut segments) in &mut snakes {
+            if player.0 == event.player && segments.0.len() > length {
+                for entity in segments.0.split_off(length) {
+                    commands.entity(entity).despawn();
+                }
+            }
+        }
     }
 
-    // food collisions
-    for (food_entity, food_pos) in &food_query {
-        if head_pos == food_pos {
-            info!("You ate a food -> Grow!");
-            commands.entity(food_entity).despawn();
-            commands.trigger(FoodEaten);
-            pending_growth.0 += 1;
-            commands.spawn((
-                AudioPlayer::new(global_assets.food_eaten_sound.clone()),
-                PlaybackSettings::ONCE,
-            ));
-        }
+    if event.kind == FoodKind::Speed {
+        speed_boost.0.reset();
     }
 
-    // self collisions
-    for segment in segments.0.iter().skip(1) {
-        let body_pos = positions.get(*segment).unwrap();
-        if head_pos == body_pos {
-            info!("You touched your own body -> Game over!");
-            next_state.set(AppState::Menu);
-            commands.spawn((
-                AudioPlayer::new(global_assets.game_over_sound.clone()),
-                PlaybackSettings::ONCE,
-            ));
-        }
+    // Versus games are won by outliving the other snake, not by points.
+    if board.snakes().len() == 1 {
+        let points = food_points(timer.0.duration().as_secs_f32(), SNAKE_MOVE_INTERVAL);
+        score.0 += points * food_type.points;
     }
+    commands.spawn((
+        AudioPlayer::new(global_assets.food_eaten_sound.clone()),
+        PlaybackSettings::ONCE.with_speed(food_type.sound_speed),
+    ));
 }
 
-fn reset_resources(
-    mut segments: ResMut<SnakeSegments>,
-    mut move_timer: ResMut<MoveTimer>,
-    mut direction: ResMut<CurrentDirection>,
-) {
-    segments.0.clear();
+fn tick_speed_boost(time: Res<Time>, mut speed_boost: ResMut<SpeedBoost>) {
+    speed_boost.0.tick(time.delta());
+}
+
+/// Keys pressed while paused are dropped instead of queued as turns.
+fn disable_controls(mut commands: Commands, heads: Query<Entity, With<SnakeHead>>) {
+    for head in &heads {
+        commands
+            .entity(head)
+            .insert(ContextActivity::<SnakeHead>::INACTIVE);
+    }
+}
+
+fn enable_controls(mut commands: Commands, heads: Query<Entity, With<SnakeHead>>) {
+    for head in &heads {
+        commands
+            .entity(head)
+            .insert(ContextActivity::<SnakeHead>::ACTIVE);
+    }
+}
+
+fn reset_step_count(mut step_count: ResMut<StepCount>) {
+    step_count.0 = 0;
+}
+
+fn reset_resources(mut move_timer: ResMut<MoveTimer>, mut speed_boost: ResMut<SpeedBoost>) {
     move_timer.0.reset();
-    direction.0 = Direction::Right;
+    *speed_boost = SpeedBoost::default();
+}
+
+/// Speeds the snakes up as the longest one grows, following the current
+/// level's curve, and faster still while a speed food is active.
+fn apply_speed_curve(
+    board: Res<Board>,
+    level: Res<CurrentLevel>,
+    speed_boost: Res<SpeedBoost>,
+    mut timer: ResMut<MoveTimer>,
+) {
+    let longest = board.snakes().iter().map(|snake| snake.length()).max();
+    let extra_segments = longest.unwrap_or(0).saturating_sub(START_LENGTH);
+    let mut interval = level.get().speed.interval(extra_segments);
+    if !speed_boost.0.is_finished() {
+        interval *= SPEED_BOOST_FACTOR;
+    }
+    timer.0.set_duration(Duration::from_secs_f32(interval));
 }
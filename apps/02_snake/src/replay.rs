@@ -0,0 +1,816 @@
+//! Run recordings. Every heading a snake takes and every food that runs out is
+//! written down with the step it happened on, next to the seed and the menu
+//! settings, so stepping the same board again plays the same run. The last run
+//! can be watched from the menu, and the best one races along as a ghost.
+
+use crate::autopilot::AttractMode;
+use crate::board::{Direction, SnakeBoard, StepOutcome};
+use crate::food::{FoodExpired, FoodKind, place_random_food};
+use crate::grid::{BoardSize, GridPos, GridSize, TILE_SIZE, WallRule};
+use crate::level::{CurrentLevel, LEVELS, load_level_map};
+use crate::line_file::{self, LineError};
+use crate::player::{Board, GameMode, MoveTimer, StepCount, move_snake};
+use crate::save::ResumedGame;
+use crate::score::Score;
+use crate::seed::{GameRng, seed_rng};
+use crate::{AppState, AppSystems, AssetState, PausableSystems};
+use bevy::prelude::*;
+use bevy_enhanced_input::actions;
+use bevy_enhanced_input::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Bumped whenever old replays would play differently.
+const VERSION: u32 = 1;
+pub const LAST_RUN_PATH: &str = "snake_last_run.replay";
+const BEST_RUN_PATH: &str = "snake_best_run.replay";
+const GHOST_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.35);
+/// Between the food and the snakes.
+const GHOST_Z: f32 = 1.5;
+const MIN_PLAYBACK_SPEED: f32 = 0.25;
+const MAX_PLAYBACK_SPEED: f32 = 8.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShowGhost>();
+    app.add_input_context::<ReplayViewer>();
+    app.add_observer(record_expired_food);
+    app.add_observer(toggle_pause);
+    app.add_observer(step_once);
+    app.add_observer(speed_up);
+    app.add_observer(slow_down);
+    app.add_observer(stop_watching);
+
+    app.add_systems(Startup, load_best_run);
+    app.add_systems(OnEnter(AssetState::Loading), setup);
+    app.add_systems(OnEnter(AppState::Menu), leave_replay);
+    app.add_systems(
+        OnTransition {
+            exited: AppState::Menu,
+            entered: AppState::Playing,
+        },
+        (start_recording, seed_playback.after(seed_rng)),
+    );
+    app.add_systems(
+        OnEnter(AppState::Playing),
+        (remember_headings, start_ghost, spawn_viewer).after(load_level_map),
+    );
+    app.add_systems(OnEnter(AppState::GameOver), finish_recording);
+    app.add_systems(OnEnter(AppState::Won), finish_recording);
+    app.add_systems(
+        Update,
+        (
+            feed_playback
+                .before(move_snake)
+                .run_if(resource_exists::<Playback>),
+            record_turns
+                .after(move_snake)
+                .run_if(resource_exists::<Recording>.and(resource_changed::<StepCount>)),
+            advance_ghost
+                .after(move_snake)
+                .run_if(resource_exists::<GhostRun>.and(resource_changed::<StepCount>)),
+        )
+            .run_if(in_state(AppState::Playing))
+            .in_set(AppSystems::Update)
+            .in_set(PausableSystems),
+    );
+    app.add_systems(
+        Update,
+        (
+            sync_ghost.run_if(resource_exists::<GhostRun>),
+            update_viewer_text.run_if(resource_exists::<Playback>),
+        )
+            .run_if(in_state(AppState::Playing))
+            .in_set(AppSystems::Render),
+    );
+}
+
+/// Something that changes how the run goes, besides the seed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReplayEvent {
+    /// The snake took this heading on the step.
+    Turn { player: usize, direction: Direction },
+    /// The timed food ran out before the step.
+    FoodExpired,
+}
+
+/// One recorded run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: GameMode,
+    pub wall_rule: WallRule,
+    pub board_size: BoardSize,
+    pub score: u32,
+    /// Events with the number of steps taken before them, in order.
+    pub events: Vec<(u64, ReplayEvent)>,
+}
+
+impl Replay {
+    /// The replay file: a version line, one line per setting, then one line
+    /// per event, e.g. `turn 12 0 up` or `expired 40`.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "snake-replay {VERSION}\nseed {}\nmode {}\nwalls {}\nboard {}\nscore {}\n",
+            self.seed,
+            self.mode.label(),
+            self.wall_rule.label(),
+            board_size_name(self.board_size),
+            self.score
+        );
+        for (step, event) in &self.events {
+            let line = match event {
+                ReplayEvent::Turn { player, direction } => {
+                    format!("turn {step} {player} {}\n", direction_name(*direction))
+                }
+                ReplayEvent::FoodExpired => format!("expired {step}\n"),
+            };
+            text.push_str(&line);
+        }
+        text
+    }
+
+    pub fn parse(source: &str) -> Result<Self, LineError> {
+        let mut replay = Replay::default();
+        for (line, text) in line_file::lines(source, "replay", VERSION)? {
+            let number = |word: &str| {
+                word.parse::<u64>()
+                    .map_err(|_| LineError::new(line, format!("`{word}` is not a number")))
+            };
+            let (step, event) = match text.split_whitespace().collect::<Vec<_>>()[..] {
+                ["seed", seed] => {
+                    replay.seed = number(seed)?;
+                    continue;
+                }
+                ["mode", mode] => {
+                    replay.mode = GameMode::from_label(mode)
+                        .ok_or_else(|| LineError::new(line, format!("unknown mode `{mode}`")))?;
+                    continue;
+                }
+                ["walls", walls] => {
+                    replay.wall_rule = WallRule::from_label(walls)
+                        .ok_or_else(|| LineError::new(line, format!("unknown walls `{walls}`")))?;
+                    continue;
+                }
+                ["board", board] => {
+                    replay.board_size = parse_board_size(board)
+                        .ok_or_else(|| LineError::new(line, format!("unknown board `{board}`")))?;
+                    continue;
+                }
+                ["score", score] => {
+                    replay.score = number(score)? as u32;
+                    continue;
+                }
+                ["turn", step, player, direction] => {
+                    let player = number(player)? as usize;
+                    // The mode line comes before the events.
+                    if player >= replay.mode.players() {
+                        return Err(LineError::new(line, format!("no player {player}")));
+                    }
+                    let Some(direction) = parse_direction(direction) else {
+                        return Err(LineError::new(
+                            line,
+                            format!("unknown direction `{direction}`"),
+                        ));
+                    };
+                    (number(step)?, ReplayEvent::Turn { player, direction })
+                }
+                ["expired", step] => (number(step)?, ReplayEvent::FoodExpired),
+                _ => return Err(LineError::new(line, format!("unexpected `{text}`"))),
+            };
+
+            if replay.events.last().is_some_and(|&(last, _)| last > step) {
+                return Err(LineError::new(line, "events are out of order"));
+            }
+            replay.events.push((step, event));
+        }
+        Ok(replay)
+    }
+
+    fn load(path: &str) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        Replay::parse(&source).map_err(|error| error.to_string())
+    }
+
+    fn save(&self, path: &str) {
+        if let Err(error) = std::fs::write(path, self.to_text()) {
+            warn!("Failed to save the replay to {path}: {error}");
+        }
+    }
+}
+
+pub fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+pub fn parse_direction(name: &str) -> Option<Direction> {
+    Direction::ALL
+        .into_iter()
+        .find(|&direction| direction_name(direction) == name)
+}
+
+pub fn board_size_name(board_size: BoardSize) -> String {
+    match board_size {
+        BoardSize::Level => "level".to_string(),
+        BoardSize::Small => "small".to_string(),
+        BoardSize::Medium => "medium".to_string(),
+        BoardSize::Large => "large".to_string(),
+        BoardSize::Custom(size) => format!("{}x{}", size.width, size.height),
+    }
+}
+
+pub fn parse_board_size(text: &str) -> Option<BoardSize> {
+    match text {
+        "level" => Some(BoardSize::Level),
+        "small" => Some(BoardSize::Small),
+        "medium" => Some(BoardSize::Medium),
+        "large" => Some(BoardSize::Large),
+        _ => {
+            let (width, height) = text.split_once('x')?;
+            Some(BoardSize::Custom(GridSize {
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+            }))
+        }
+    }
+}
+
+/// The run being played, written down as it goes.
+#[derive(Resource)]
+struct Recording {
+    replay: Replay,
+    /// Each snake's heading after the last step.
+    headings: Vec<Direction>,
+}
+
+/// The replay being watched. Nobody steers while it exists.
+#[derive(Resource)]
+pub struct Playback {
+    replay: Replay,
+    /// Index of the next event to feed to the board.
+    next_event: usize,
+}
+
+impl Playback {
+    /// Loads a replay and applies its settings, ready to start playing.
+    pub fn start(
+        path: &str,
+        mode: &mut GameMode,
+        wall_rule: &mut WallRule,
+        board_size: &mut BoardSize,
+    ) -> Result<Self, String> {
+        let replay = Replay::load(path)?;
+        *mode = replay.mode;
+        *wall_rule = replay.wall_rule;
+        *board_size = replay.board_size;
+        Ok(Self {
+            replay,
+            next_event: 0,
+        })
+    }
+}
+
+/// Best solo run so far, loaded from and saved to [`BEST_RUN_PATH`].
+#[derive(Resource, Default)]
+struct BestRun(Option<Replay>);
+
+/// Whether the best run plays along as a ghost, toggled in the menu.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShowGhost(pub bool);
+
+/// The best run, stepped alongside the live game on its own board. It is
+/// step-synced: it moves one step for every live step, not on the recorded
+/// run's own timing, so it only shows where the best run was after as many
+/// moves. It only knows the first level and is gone once it crashes or clears it.
+#[derive(Resource)]
+struct GhostRun {
+    board: SnakeBoard,
+    rng: StdRng,
+    food: Option<FoodKind>,
+    food_eaten: u32,
+    food_target: Option<u32>,
+    events: Vec<(u64, ReplayEvent)>,
+    next_event: usize,
+    steps: u64,
+    finished: bool,
+}
+
+impl GhostRun {
+    /// Starts `replay` on a copy of `board`, as it was before any food.
+    fn new(board: &SnakeBoard, replay: &Replay, food_target: Option<u32>) -> Self {
+        let mut board = board.clone();
+        board.remove_food();
+        // Seeded like [`GameRng`], so the food comes in the same order.
+        let mut rng = StdRng::seed_from_u64(replay.seed);
+        let food = place_random_food(&mut board, &mut rng).map(|(_, kind)| kind);
+
+        Self {
+            board,
+            rng,
+            food,
+            food_eaten: 0,
+            food_target,
+            events: replay.events.clone(),
+            next_event: 0,
+            steps: 0,
+            finished: false,
+        }
+    }
+
+    /// Takes one step, the same way the live game does.
+    fn advance(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        while let Some(&(step, event)) = self.events.get(self.next_event)
+            && step <= self.steps
+        {
+            match event {
+                ReplayEvent::Turn { player, direction } => {
+                    self.board.turn(player, direction);
+                }
+                ReplayEvent::FoodExpired => {
+                    self.board.remove_food();
+                    self.place_food();
+                }
+            }
+            self.next_event += 1;
+        }
+
+        self.steps += 1;
+        match self.board.step()[0] {
+            StepOutcome::Moved => {}
+            StepOutcome::Died(_) => self.finished = true,
+            StepOutcome::Ate => {
+                let food_type = self.food.unwrap_or(FoodKind::Normal).food_type();
+                if food_type.growth >= 0 {
+                    self.board.grow(0, food_type.growth.unsigned_abs());
+                    self.food_eaten += 1;
+                } else {
+                    self.board.shrink(0, food_type.growth.unsigned_abs());
+                }
+                self.place_food();
+                if self
+                    .food_target
+                    .is_some_and(|target| self.food_eaten >= target)
+                {
+                    self.finished = true;
+                }
+            }
+        }
+    }
+
+    fn place_food(&mut self) {
+        self.food = place_random_food(&mut self.board, &mut self.rng).map(|(_, kind)| kind);
+        if self.food.is_none() {
+            self.finished = true;
+        }
+    }
+
+    /// Cells of the ghost snake, head first, empty once it's gone.
+    fn body(&self) -> impl Iterator<Item = GridPos> + '_ {
+        self.board
+            .snakes()
+            .first()
+            .filter(|_| !self.finished)
+            .into_iter()
+            .flat_map(|snake| snake.body().iter().copied())
+    }
+}
+
+#[derive(Component)]
+struct GhostSegment;
+
+#[derive(Resource)]
+struct GhostAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+/// Bar of playback controls shown while watching.
+#[derive(Component)]
+struct ReplayViewer;
+
+#[derive(Component)]
+struct ReplayViewerText;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct TogglePauseAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct StepAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct SpeedUpAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct SlowDownAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct StopWatchingAction;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(GhostAssets {
+        mesh: meshes.add(Rectangle::new(TILE_SIZE * 0.7, TILE_SIZE * 0.7)),
+        material: materials.add(ColorMaterial::from_color(GHOST_COLOR)),
+    });
+}
+
+fn load_best_run(mut commands: Commands) {
+    let best_run = match Replay::load(BEST_RUN_PATH) {
+        Ok(replay) => Some(replay),
+        Err(error) => {
+            if std::fs::exists(BEST_RUN_PATH).unwrap_or(false) {
+                warn!("Ignoring the best run replay: {error}");
+            }
+            None
+        }
+    };
+    commands.insert_resource(BestRun(best_run));
+}
+
+fn start_recording(
+    mut commands: Commands,
+    attract_mode: Res<AttractMode>,
+    playback: Option<Res<Playback>>,
+    resumed: Option<Res<ResumedGame>>,
+    mode: Res<GameMode>,
+    wall_rule: Res<WallRule>,
+    board_size: Res<BoardSize>,
+) {
+    // Demos and replays aren't worth keeping, and a continued game has lost
+    // its start.
+    if attract_mode.0 || playback.is_some() || resumed.is_some() {
+        return;
+    }
+
+    commands.insert_resource(Recording {
+        replay: Replay {
+            mode: *mode,
+            wall_rule: *wall_rule,
+            board_size: *board_size,
+            ..default()
+        },
+        headings: Vec::new(),
+    });
+}
+
+/// Reseeds with the replay's seed instead of the menu's.
+fn seed_playback(playback: Option<Res<Playback>>, mut rng: ResMut<GameRng>) {
+    if let Some(playback) = playback {
+        *rng = GameRng::new(playback.replay.seed);
+    }
+}
+
+/// Each level starts with fresh snakes, so headings are compared from there.
+fn remember_headings(board: Res<Board>, recording: Option<ResMut<Recording>>) {
+    if let Some(mut recording) = recording {
+        recording.headings = board.snakes().iter().map(|snake| snake.heading()).collect();
+    }
+}
+
+/// Writes down every heading that changed on the last step.
+fn record_turns(board: Res<Board>, step_count: Res<StepCount>, mut recording: ResMut<Recording>) {
+    let Some(step) = step_count.0.checked_sub(1) else {
+        return;
+    };
+    let Recording { replay, headings } = &mut *recording;
+    for (player, (snake, heading)) in board.snakes().iter().zip(headings).enumerate() {
+        if snake.heading() != *heading {
+            *heading = snake.heading();
+            replay.events.push((
+                step,
+                ReplayEvent::Turn {
+                    player,
+                    direction: *heading,
+                },
+            ));
+        }
+    }
+}
+
+fn record_expired_food(
+    _event: On<FoodExpired>,
+    step_count: Res<StepCount>,
+    recording: Option<ResMut<Recording>>,
+) {
+    if let Some(mut recording) = recording {
+        recording
+            .replay
+            .events
+            .push((step_count.0, ReplayEvent::FoodExpired));
+    }
+}
+
+/// Saves the run as the last one, and as the best one if it beat it.
+fn finish_recording(
+    mut commands: Commands,
+    recording: Option<Res<Recording>>,
+    rng: Res<GameRng>,
+    score: Res<Score>,
+    mut best_run: ResMut<BestRun>,
+) {
+    let Some(recording) = recording else {
+        return;
+    };
+    commands.remove_resource::<Recording>();
+
+    let replay = Replay {
+        seed: rng.seed,
+        score: score.0,
+        ..recording.replay.clone()
+    };
+    replay.save(LAST_RUN_PATH);
+
+    let is_best = match &best_run.0 {
+        Some(best) => replay.score > best.score,
+        None => replay.score > 0,
+    };
+    if replay.mode == GameMode::Solo && is_best {
+        info!("New best run, saved for the ghost");
+        replay.save(BEST_RUN_PATH);
+        best_run.0 = Some(replay);
+    }
+}
+
+/// Hands the board the events due before the next step.
+fn feed_playback(
+    mut commands: Commands,
+    step_count: Res<StepCount>,
+    mut board: ResMut<Board>,
+    mut playback: ResMut<Playback>,
+) {
+    let Playback { replay, next_event } = &mut *playback;
+    while let Some(&(step, event)) = replay.events.get(*next_event)
+        && step <= step_count.0
+    {
+        match event {
+            ReplayEvent::Turn { player, direction } => {
+                board.turn(player, direction);
+            }
+            ReplayEvent::FoodExpired => commands.trigger(FoodExpired),
+        }
+        *next_event += 1;
+    }
+}
+
+/// Races the best run on the first level of a solo game with its settings.
+fn start_ghost(
+    mut commands: Commands,
+    show_ghost: Res<ShowGhost>,
+    best_run: Res<BestRun>,
+    board: Res<Board>,
+    level: Res<CurrentLevel>,
+    attract_mode: Res<AttractMode>,
+    playback: Option<Res<Playback>>,
+    resumed: Option<Res<ResumedGame>>,
+    mode: Res<GameMode>,
+    wall_rule: Res<WallRule>,
+    board_size: Res<BoardSize>,
+) {
+    commands.remove_resource::<GhostRun>();
+    let Some(best) = &best_run.0 else {
+        return;
+    };
+    let same_game =
+        best.mode == *mode && best.wall_rule == *wall_rule && best.board_size == *board_size;
+    if !show_ghost.0
+        || *mode != GameMode::Solo
+        || !same_game
+        || level.0 != 0
+        || attract_mode.0
+        || playback.is_some()
+        || resumed.is_some()
+    {
+        return;
+    }
+
+    commands.insert_resource(GhostRun::new(&board, best, LEVELS[0].food_target));
+}
+
+fn advance_ghost(step_count: Res<StepCount>, mut ghost: ResMut<GhostRun>) {
+    while ghost.steps < step_count.0 && !ghost.finished {
+        ghost.advance();
+    }
+}
+
+/// Keeps one ghost segment per cell of the ghost snake.
+fn sync_ghost(
+    mut commands: Commands,
+    ghost: Res<GhostRun>,
+    ghost_assets: Res<GhostAssets>,
+    size: Res<GridSize>,
+    mut segments: Query<(Entity, &mut Transform), With<GhostSegment>>,
+) {
+    let mut body = ghost.body();
+    for (entity, mut transform) in &mut segments {
+        match body.next() {
+            Some(pos) => transform.translation = size.to_world(pos).with_z(GHOST_Z),
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    for pos in body {
+        commands.spawn((
+            GhostSegment,
+            Mesh2d(ghost_assets.mesh.clone()),
+            MeshMaterial2d(ghost_assets.material.clone()),
+            Transform::from_translation(size.to_world(pos).with_z(GHOST_Z)),
+            DespawnOnExit(AppState::Playing),
+        ));
+    }
+}
+
+fn spawn_viewer(mut commands: Commands, playback: Option<Res<Playback>>) {
+    if playback.is_none() {
+        return;
+    }
+
+    commands.spawn((
+        ReplayViewer,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(20),
+            width: vw(100),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        DespawnOnExit(AppState::Playing),
+        actions!(
+            ReplayViewer[
+                (
+                    Action::<TogglePauseAction>::new(),
+                    bindings![KeyCode::Space]
+                ),
+                (
+                    Action::<StepAction>::new(),
+                    bindings![KeyCode::ArrowRight, KeyCode::Period]
+                ),
+                (
+                    Action::<SpeedUpAction>::new(),
+                    bindings![KeyCode::ArrowUp]
+                ),
+                (
+                    Action::<SlowDownAction>::new(),
+                    bindings![KeyCode::ArrowDown]
+                ),
+                (
+                    Action::<StopWatchingAction>::new(),
+                    bindings![KeyCode::Escape]
+                ),
+            ]
+        ),
+        children![(ReplayViewerText, Text::default())],
+    ));
+}
+
+fn update_viewer_text(
+    time: Res<Time<Virtual>>,
+    mut text: Single<&mut Text, With<ReplayViewerText>>,
+) {
+    let state = if time.is_paused() {
+        "paused".to_string()
+    } else {
+        format!("{}x", time.relative_speed())
+    };
+    text.set_if_neq(Text::new(format!(
+        "Replay ({state}) - 'space' pause, 'right' step, 'up'/'down' speed, 'esc' menu"
+    )));
+}
+
+fn toggle_pause(_event: On<Start<TogglePauseAction>>, mut time: ResMut<Time<Virtual>>) {
+    if time.is_paused() {
+        time.unpause();
+    } else {
+        time.pause();
+    }
+}
+
+/// Takes a single step while paused.
+fn step_once(
+    _event: On<Start<StepAction>>,
+    time: Res<Time<Virtual>>,
+    mut timer: ResMut<MoveTimer>,
+) {
+    if time.is_paused() {
+        let duration = timer.0.duration();
+        timer.0.set_elapsed(duration);
+    }
+}
+
+fn speed_up(_event: On<Start<SpeedUpAction>>, mut time: ResMut<Time<Virtual>>) {
+    let speed = (time.relative_speed() * 2.0).min(MAX_PLAYBACK_SPEED);
+    time.set_relative_speed(speed);
+}
+
+fn slow_down(_event: On<Start<SlowDownAction>>, mut time: ResMut<Time<Virtual>>) {
+    let speed = (time.relative_speed() / 2.0).max(MIN_PLAYBACK_SPEED);
+    time.set_relative_speed(speed);
+}
+
+fn stop_watching(
+    _event: On<Start<StopWatchingAction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    next_state.set(AppState::Menu);
+}
+
+/// Drops the replay and unfinished recordings, and puts the clock back to normal.
+fn leave_replay(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
+    commands.remove_resource::<Playback>();
+    commands.remove_resource::<Recording>();
+    commands.remove_resource::<GhostRun>();
+    time.unpause();
+    time.set_relative_speed(1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::LevelMap;
+
+    fn replay() -> Replay {
+        Replay {
+            seed: 42,
+            mode: GameMode::Versus,
+            wall_rule: WallRule::Wrap,
+            board_size: BoardSize::Custom(GridSize {
+                width: 15,
+                height: 9,
+            }),
+            score: 12,
+            events: vec![
+                (
+                    0,
+                    ReplayEvent::Turn {
+                        player: 1,
+                        direction: Direction::Up,
+                    },
+                ),
+                (3, ReplayEvent::FoodExpired),
+            ],
+        }
+    }
+
+    #[test]
+    fn replays_survive_a_round_trip() {
+        let replay = replay();
+
+        assert_eq!(Replay::parse(&replay.to_text()).unwrap(), replay);
+    }
+
+    #[test]
+    fn rejects_bad_events() {
+        let error = Replay::parse("snake-replay 1\nseed 1\nturn 4 0 sideways\n").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.message, "unknown direction `sideways`");
+
+        let error = Replay::parse("snake-replay 1\nexpired 5\nexpired 2\n").unwrap_err();
+        assert_eq!(error.line, 3);
+
+        let solo = replay().to_text().replace("mode versus", "mode solo");
+        let error = Replay::parse(&solo).unwrap_err();
+        assert_eq!(error.line, 7);
+        assert_eq!(error.message, "no player 1");
+    }
+
+    #[test]
+    fn ghost_follows_the_recorded_turns() {
+        let board = SnakeBoard::new(LevelMap::default(), WallRule::Solid);
+        let replay = Replay {
+            events: vec![(
+                1,
+                ReplayEvent::Turn {
+                    player: 0,
+                    direction: Direction::Up,
+                },
+            )],
+            ..default()
+        };
+        let mut ghost = GhostRun::new(&board, &replay, None);
+
+        ghost.advance();
+        ghost.advance();
+        // One step right from (3, 4), then one up.
+        assert_eq!(ghost.body().next(), Some(GridPos { x: 4, y: 5 }));
+
+        // Up into the top wall, four rows further.
+        for _ in 0..4 {
+            ghost.advance();
+        }
+        assert_eq!(ghost.body().next(), None);
+    }
+}
@@ -0,0 +1,409 @@
+//! Saved games. Quitting from the pause menu writes the game down, and
+//! "continue" in the menu picks it up on the same step, with the same food to
+//! come.
+
+use crate::AppState;
+use crate::board::{Snake, SnakeBoard};
+use crate::food::{FOOD_TYPES, Food, FoodKind};
+use crate::grid::{BoardSize, GridPos, WallRule};
+use crate::level::{CurrentLevel, LEVELS, LevelProgress, load_level_map, reset_progress};
+use crate::line_file::{self, LineError};
+use crate::player::{Board, GameMode, spawn_player};
+use crate::replay::{board_size_name, direction_name, parse_board_size, parse_direction};
+use crate::score::Score;
+use crate::seed::GameRng;
+use bevy::prelude::*;
+
+/// Written in the header line, see [`line_file`].
+const VERSION: u32 = 1;
+const SAVE_PATH: &str = "snake_saved_game.save";
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(save_game);
+
+    app.add_systems(
+        OnEnter(AppState::Playing),
+        (
+            restore_run.after(reset_progress).before(load_level_map),
+            restore_board.after(load_level_map).before(spawn_player),
+        )
+            .run_if(resource_exists::<ResumedGame>),
+    );
+    app.add_systems(OnExit(AppState::Playing), finish_resume);
+}
+
+/// A game stopped in the middle of a level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedGame {
+    pub mode: GameMode,
+    pub wall_rule: WallRule,
+    pub board_size: BoardSize,
+    /// Index into [`LEVELS`].
+    pub level: usize,
+    pub level_progress: u32,
+    pub score: u32,
+    pub seed: u64,
+    /// See [`GameRng::checkpoint`].
+    pub rng_state: u64,
+    /// Indexed by player.
+    pub snakes: Vec<Snake>,
+    pub food: (GridPos, FoodKind),
+}
+
+impl SavedGame {
+    /// The save file: a version line, then one line per value, e.g.
+    /// `snake right 0 3,4 2,4 1,4` with the heading, pending growth and the
+    /// cells head first, or `food 6,2 golden`.
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "snake-save {VERSION}\nmode {}\nwalls {}\nboard {}\nlevel {}\nprogress {}\nscore {}\nseed {}\nrng {}\n",
+            self.mode.label(),
+            self.wall_rule.label(),
+            board_size_name(self.board_size),
+            self.level + 1,
+            self.level_progress,
+            self.score,
+            self.seed,
+            self.rng_state
+        );
+        for snake in &self.snakes {
+            let cells: Vec<String> = snake.body().iter().map(|&pos| cell_name(pos)).collect();
+            text.push_str(&format!(
+                "snake {} {} {}\n",
+                direction_name(snake.heading()),
+                snake.pending_growth(),
+                cells.join(" ")
+            ));
+        }
+        let (pos, kind) = self.food;
+        text.push_str(&format!("food {} {}\n", cell_name(pos), food_name(kind)));
+        text
+    }
+
+    pub fn parse(source: &str) -> Result<Self, LineError> {
+        let mut mode = GameMode::default();
+        let mut wall_rule = WallRule::default();
+        let mut board_size = BoardSize::default();
+        let mut level = 0;
+        let mut level_progress = 0;
+        let mut score = 0;
+        let mut seed = 0;
+        let mut rng_state = None;
+        let mut snakes = Vec::new();
+        let mut food = None;
+        let mut last_line = 1;
+        for (line, text) in line_file::lines(source, "save", VERSION)? {
+            last_line = line;
+            let number = |word: &str| {
+                word.parse::<u64>()
+                    .map_err(|_| LineError::new(line, format!("`{word}` is not a number")))
+            };
+            let cell = |word: &str| {
+                parse_cell(word)
+                    .ok_or_else(|| LineError::new(line, format!("`{word}` is not a cell")))
+            };
+            match text.split_whitespace().collect::<Vec<_>>()[..] {
+                ["mode", name] => {
+                    mode = GameMode::from_label(name)
+                        .ok_or_else(|| LineError::new(line, format!("unknown mode `{name}`")))?;
+                }
+                ["walls", name] => {
+                    wall_rule = WallRule::from_label(name)
+                        .ok_or_else(|| LineError::new(line, format!("unknown walls `{name}`")))?;
+                }
+                ["board", name] => {
+                    board_size = parse_board_size(name)
+                        .ok_or_else(|| LineError::new(line, format!("unknown board `{name}`")))?;
+                }
+                ["level", value] => {
+                    let level_number = number(value)? as usize;
+                    if !(1..=LEVELS.len()).contains(&level_number) {
+                        return Err(LineError::new(line, format!("no level {level_number}")));
+                    }
+                    level = level_number - 1;
+                }
+                ["progress", progress] => level_progress = number(progress)? as u32,
+                ["score", points] => score = number(points)? as u32,
+                ["seed", value] => seed = number(value)?,
+                ["rng", value] => rng_state = Some(number(value)?),
+                ["snake", heading, growth, ref cells @ ..] if !cells.is_empty() => {
+                    if snakes.len() == 2 {
+                        return Err(LineError::new(line, "more than two snakes"));
+                    }
+                    let Some(heading) = parse_direction(heading) else {
+                        return Err(LineError::new(
+                            line,
+                            format!("unknown direction `{heading}`"),
+                        ));
+                    };
+                    let body = cells
+                        .iter()
+                        .map(|word| cell(word))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    snakes.push(Snake::restored(body, heading, number(growth)? as u32));
+                }
+                ["food", pos, name] => {
+                    let Some(kind) = FOOD_TYPES
+                        .iter()
+                        .map(|food_type| food_type.kind)
+                        .find(|&kind| food_name(kind) == name)
+                    else {
+                        return Err(LineError::new(line, format!("unknown food `{name}`")));
+                    };
+                    food = Some((cell(pos)?, kind));
+                }
+                _ => return Err(LineError::new(line, format!("unexpected `{text}`"))),
+            }
+        }
+
+        let Some(rng_state) = rng_state else {
+            return Err(LineError::new(last_line, "the random generator is missing"));
+        };
+        if snakes.is_empty() {
+            return Err(LineError::new(last_line, "there is no snake"));
+        }
+        let Some(food) = food else {
+            return Err(LineError::new(last_line, "there is no food"));
+        };
+        Ok(Self {
+            mode,
+            wall_rule,
+            board_size,
+            level,
+            level_progress,
+            score,
+            seed,
+            rng_state,
+            snakes,
+            food,
+        })
+    }
+
+    /// `board` with the saved snakes on it, or why they don't fit its map.
+    fn fit(&self, board: &SnakeBoard) -> Result<SnakeBoard, String> {
+        if self.snakes.len() != board.snakes().len() {
+            return Err(format!(
+                "it has {} snakes, the map has room for {}",
+                self.snakes.len(),
+                board.snakes().len()
+            ));
+        }
+        let mut cells = self.snakes.iter().flat_map(|snake| snake.body());
+        if let Some(&pos) = cells.find(|&&pos| !board.cell(pos).is_passable()) {
+            return Err(format!("a snake is off the floor on {}", cell_name(pos)));
+        }
+
+        let mut fitted = board.clone();
+        fitted.replace_snakes(self.snakes.clone());
+        let (food, _) = self.food;
+        if !fitted.is_free(food) {
+            return Err(format!(
+                "the food is not on a free cell, {}",
+                cell_name(food)
+            ));
+        }
+        Ok(fitted)
+    }
+
+    fn load() -> Result<Self, String> {
+        let source = std::fs::read_to_string(SAVE_PATH).map_err(|error| error.to_string())?;
+        SavedGame::parse(&source).map_err(|error| error.to_string())
+    }
+}
+
+fn cell_name(pos: GridPos) -> String {
+    format!("{},{}", pos.x, pos.y)
+}
+
+fn parse_cell(text: &str) -> Option<GridPos> {
+    let (x, y) = text.split_once(',')?;
+    Some(GridPos {
+        x: x.parse().ok()?,
+        y: y.parse().ok()?,
+    })
+}
+
+fn food_name(kind: FoodKind) -> String {
+    format!("{kind:?}").to_lowercase()
+}
+
+/// Saves the game being played to [`SAVE_PATH`].
+#[derive(Event)]
+pub struct SaveGame;
+
+/// The saved game being continued, until the level it was saved on ends.
+#[derive(Resource, Deref)]
+pub struct ResumedGame(SavedGame);
+
+impl ResumedGame {
+    /// Loads the saved game and applies its settings, ready to start playing.
+    /// The save is used up: quitting again saves anew.
+    pub fn start(
+        mode: &mut GameMode,
+        wall_rule: &mut WallRule,
+        board_size: &mut BoardSize,
+    ) -> Result<Self, String> {
+        let game = SavedGame::load()?;
+        *mode = game.mode;
+        *wall_rule = game.wall_rule;
+        *board_size = game.board_size;
+        if let Err(error) = std::fs::remove_file(SAVE_PATH) {
+            warn!("Failed to remove the saved game: {error}");
+        }
+        Ok(Self(game))
+    }
+}
+
+/// Whether there is a game to continue.
+pub fn has_saved_game() -> bool {
+    std::fs::exists(SAVE_PATH).unwrap_or(false)
+}
+
+fn save_game(
+    _event: On<SaveGame>,
+    board: Res<Board>,
+    food: Query<(&GridPos, &Food)>,
+    mode: Res<GameMode>,
+    wall_rule: Res<WallRule>,
+    board_size: Res<BoardSize>,
+    level: Res<CurrentLevel>,
+    progress: Res<LevelProgress>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Some((&pos, food)) = food.iter().next() else {
+        warn!("Not saving a game without food");
+        return;
+    };
+
+    let game = SavedGame {
+        mode: *mode,
+        wall_rule: *wall_rule,
+        board_size: *board_size,
+        level: level.0,
+        level_progress: progress.0,
+        score: score.0,
+        seed: rng.seed,
+        rng_state: rng.checkpoint(),
+        snakes: board.snakes().to_vec(),
+        food: (pos, food.0),
+    };
+    match std::fs::write(SAVE_PATH, game.to_text()) {
+        Ok(()) => info!("Game saved to {SAVE_PATH}"),
+        Err(error) => warn!("Failed to save the game to {SAVE_PATH}: {error}"),
+    }
+}
+
+/// Puts back what the level's board is set up from.
+fn restore_run(
+    resumed: Res<ResumedGame>,
+    mut level: ResMut<CurrentLevel>,
+    mut progress: ResMut<LevelProgress>,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+) {
+    level.0 = resumed.level;
+    progress.0 = resumed.level_progress;
+    score.0 = resumed.score;
+    *rng = GameRng::resume(resumed.seed, resumed.rng_state);
+}
+
+/// Swaps the fresh snakes for the saved ones. A save that doesn't fit the map
+/// is dropped, and the level starts over instead.
+pub fn restore_board(
+    mut commands: Commands,
+    resumed: Res<ResumedGame>,
+    mut board: ResMut<Board>,
+    level: Res<CurrentLevel>,
+) {
+    match resumed.fit(&board) {
+        Ok(fitted) => board.0 = fitted,
+        Err(error) => {
+            warn!(
+                "Can't continue the saved game, starting level {} over: {error}",
+                level.number()
+            );
+            commands.remove_resource::<ResumedGame>();
+        }
+    }
+}
+
+fn finish_resume(mut commands: Commands) {
+    commands.remove_resource::<ResumedGame>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Direction;
+    use crate::map::LevelMap;
+
+    fn pos(x: i32, y: i32) -> GridPos {
+        GridPos { x, y }
+    }
+
+    fn saved_game() -> SavedGame {
+        SavedGame {
+            mode: GameMode::Solo,
+            wall_rule: WallRule::Wrap,
+            board_size: BoardSize::Level,
+            level: 1,
+            level_progress: 3,
+            score: 14,
+            seed: 42,
+            rng_state: 123_456_789,
+            snakes: vec![Snake::restored(
+                [pos(5, 6), pos(5, 5), pos(4, 5)],
+                Direction::Up,
+                2,
+            )],
+            food: (pos(8, 2), FoodKind::Golden),
+        }
+    }
+
+    #[test]
+    fn parses_what_it_writes() {
+        let game = saved_game();
+        let text = game.to_text();
+
+        assert!(text.contains("\nsnake up 2 5,6 5,5 4,5\nfood 8,2 golden\n"));
+        assert_eq!(SavedGame::parse(&text).unwrap(), game);
+    }
+
+    #[test]
+    fn needs_cells_a_generator_a_snake_and_food() {
+        let error = SavedGame::parse("snake-save 1\nrng 1\nsnake up 0 3,4 3;3\n").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.message, "`3;3` is not a cell");
+
+        let error = SavedGame::parse("snake-save 1\nrng 1\nsnake up 0 3,4\n").unwrap_err();
+        assert_eq!(error.message, "there is no food");
+
+        let error = SavedGame::parse("snake-save 1\nrng 1\nfood 1,1 normal\n").unwrap_err();
+        assert_eq!(error.message, "there is no snake");
+
+        let error = SavedGame::parse("snake-save 1\nsnake up 0 3,4\n").unwrap_err();
+        assert_eq!(error.message, "the random generator is missing");
+    }
+
+    #[test]
+    fn refuses_snakes_that_dont_fit_the_map() {
+        let board = SnakeBoard::new(LevelMap::default(), WallRule::Solid);
+        let game = saved_game();
+        let fitted = game.fit(&board).unwrap();
+        assert_eq!(fitted.snake(0).head(), pos(5, 6));
+        assert_eq!(fitted.snake(0).pending_growth(), 2);
+
+        let off_board = SavedGame {
+            snakes: vec![Snake::restored([pos(11, 4)], Direction::Right, 0)],
+            ..game.clone()
+        };
+        assert!(off_board.fit(&board).is_err());
+
+        let food_on_snake = SavedGame {
+            food: (pos(5, 5), FoodKind::Normal),
+            ..game
+        };
+        assert!(food_on_snake.fit(&board).is_err());
+    }
+}
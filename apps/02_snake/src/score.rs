@@ -0,0 +1,73 @@
+//! Score of the current game and the best score, saved to disk.
+
+use crate::AppState;
+use crate::autopilot::AttractMode;
+use crate::replay::Playback;
+use bevy::prelude::*;
+
+const SAVE_PATH: &str = "snake_high_score.txt";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Score>();
+    app.add_systems(Startup, load_high_score);
+    app.add_systems(
+        OnTransition {
+            exited: AppState::Menu,
+            entered: AppState::Playing,
+        },
+        reset_score,
+    );
+    // Demo games and replays don't count.
+    app.add_systems(
+        OnEnter(AppState::GameOver),
+        update_high_score
+            .run_if(not(resource_equals(AttractMode(true))))
+            .run_if(not(resource_exists::<Playback>)),
+    );
+    app.add_systems(
+        OnEnter(AppState::Won),
+        update_high_score
+            .run_if(not(resource_equals(AttractMode(true))))
+            .run_if(not(resource_exists::<Playback>)),
+    );
+}
+
+/// Points scored in the current game.
+#[derive(Resource, Default)]
+pub struct Score(pub u32);
+
+/// Best score so far, loaded from and saved to [`SAVE_PATH`].
+#[derive(Resource, Default)]
+pub struct HighScore(pub u32);
+
+/// Points for one food: faster snakes earn more, starting at one point at the base speed.
+pub fn food_points(move_interval: f32, base_interval: f32) -> u32 {
+    (base_interval / move_interval).round().max(1.0) as u32
+}
+
+fn load_high_score(mut commands: Commands) {
+    let high_score = match std::fs::read_to_string(SAVE_PATH) {
+        Ok(save) => save.trim().parse().unwrap_or_else(|error| {
+            warn!("Ignoring the saved high score: {error}");
+            0
+        }),
+        Err(_) => 0,
+    };
+    commands.insert_resource(HighScore(high_score));
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    score.0 = 0;
+}
+
+fn update_high_score(score: Res<Score>, mut high_score: ResMut<HighScore>) {
+    if score.0 <= high_score.0 {
+        return;
+    }
+
+    info!("New high score: {}", score.0);
+    high_score.0 = score.0;
+    if let Err(error) = std::fs::write(SAVE_PATH, format!("{}\n", high_score.0)) {
+        warn!("Failed to save the high score: {error}");
+    }
+}
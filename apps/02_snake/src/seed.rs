@@ -0,0 +1,203 @@
+//! Seeded randomness, so a run's food can be played again: pass the seed
+//! shown on the game over screen with `--seed <number>`.
+
+use crate::AppState;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Random seeds stay short enough to type back in.
+const MAX_RANDOM_SEED: u64 = 1_000_000;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(SeedMode::from_args(std::env::args().skip(1)));
+    app.init_resource::<GameRng>();
+
+    app.add_systems(
+        OnTransition {
+            exited: AppState::Menu,
+            entered: AppState::Playing,
+        },
+        seed_rng,
+    );
+}
+
+/// Where the next run's seed comes from.
+#[derive(Resource, Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SeedMode {
+    #[default]
+    Random,
+    /// The same seed for everyone on the same day.
+    Daily,
+    /// Given on the command line.
+    Fixed(u64),
+}
+
+impl SeedMode {
+    /// Reads `--seed <number>` or `--seed=<number>` from the launch arguments.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        while let Some(arg) = args.next() {
+            let value = match arg.strip_prefix("--seed") {
+                Some("") => args.next(),
+                Some(value) => value.strip_prefix('=').map(str::to_string),
+                None => continue,
+            };
+            match value.as_deref().map(str::parse) {
+                Some(Ok(seed)) => return SeedMode::Fixed(seed),
+                _ => warn!("Ignoring '{arg}', expected --seed <number>"),
+            }
+        }
+        SeedMode::Random
+    }
+
+    /// Switches between random and daily seeds, dropping a fixed one.
+    pub fn toggled(self) -> Self {
+        match self {
+            SeedMode::Daily => SeedMode::Random,
+            SeedMode::Random | SeedMode::Fixed(_) => SeedMode::Daily,
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            SeedMode::Random => "random".to_string(),
+            SeedMode::Daily => match today() {
+                Some(day) => format!("daily ({})", date_seed(day)),
+                None => "daily (no clock, random)".to_string(),
+            },
+            SeedMode::Fixed(seed) => format!("seed {seed}"),
+        }
+    }
+
+    fn seed(self) -> u64 {
+        let random_seed = || rand::random_range(0..MAX_RANDOM_SEED);
+        match self {
+            SeedMode::Random => random_seed(),
+            SeedMode::Daily => today().map_or_else(random_seed, date_seed),
+            SeedMode::Fixed(seed) => seed,
+        }
+    }
+}
+
+/// Randomness of the current run, used for all food. Reseeded from
+/// [`SeedMode`] when a game starts from the menu.
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng {
+    pub seed: u64,
+    #[deref]
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Reseeds from a number drawn from itself and returns that number, so
+    /// [`GameRng::resume`] can carry on from here in a later session.
+    pub fn checkpoint(&mut self) -> u64 {
+        let state = self.rng.next_u64();
+        self.rng = StdRng::seed_from_u64(state);
+        state
+    }
+
+    /// A run's generator as it was at a [`GameRng::checkpoint`].
+    pub fn resume(seed: u64, state: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(state),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Days since 1970-01-01 in UTC, `None` where there is no clock to ask.
+fn today() -> Option<i64> {
+    if cfg!(target_arch = "wasm32") {
+        return None;
+    }
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    Some((elapsed.as_secs() / 86_400) as i64)
+}
+
+/// The date `days` after 1970-01-01 written as a number, 20261018 for
+/// 2026-10-18, so a daily seed says which day it is for.
+pub fn date_seed(days: i64) -> u64 {
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year * 10_000 + month * 100 + day) as u64
+}
+
+pub fn seed_rng(mode: Res<SeedMode>, mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(mode.seed());
+    info!("Seed {}", rng.seed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter().map(|arg| arg.to_string())
+    }
+
+    #[test]
+    fn reads_the_seed_argument() {
+        assert_eq!(SeedMode::from_args(args(&[])), SeedMode::Random);
+        assert_eq!(
+            SeedMode::from_args(args(&["--seed", "42"])),
+            SeedMode::Fixed(42)
+        );
+        assert_eq!(
+            SeedMode::from_args(args(&["--other", "--seed=7"])),
+            SeedMode::Fixed(7)
+        );
+        assert_eq!(
+            SeedMode::from_args(args(&["--seed", "soon"])),
+            SeedMode::Random
+        );
+    }
+
+    #[test]
+    fn resuming_from_a_checkpoint_carries_on_the_same_numbers() {
+        let mut rng = GameRng::new(42);
+        rng.next_u64();
+        let state = rng.checkpoint();
+        let mut resumed = GameRng::resume(42, state);
+
+        for _ in 0..3 {
+            assert_eq!(resumed.next_u64(), rng.next_u64());
+        }
+    }
+
+    #[test]
+    fn daily_seeds_are_dates() {
+        assert_eq!(date_seed(0), 19_700_101);
+        assert_eq!(date_seed(59), 19_700_301);
+        assert_eq!(date_seed(11_017), 20_000_301);
+        assert_eq!(date_seed(20_744), 20_261_018);
+    }
+}
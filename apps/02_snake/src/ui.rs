@@ -1,27 +1,635 @@
-use crate::AssetState;
+use crate::autopilot::{AttractMode, Autopilot};
+use crate::grid::{BoardSize, WallRule};
+use crate::level::CurrentLevel;
+use crate::map::AssetErrors;
+use crate::pause::RestartRequested;
+use crate::player::{Board, GameMode, Player};
+use crate::replay::{LAST_RUN_PATH, Playback, ShowGhost};
+use crate::save::{ResumedGame, has_saved_game};
+use crate::score::{HighScore, Score};
+use crate::seed::{GameRng, SeedMode};
+use crate::{AppState, AppSystems, AssetState, GameOverInfo};
 use bevy::color::palettes::basic::RED;
 use bevy::prelude::*;
+use bevy_enhanced_input::actions;
+use bevy_enhanced_input::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
+    app.add_input_context::<MenuScreen>();
+    app.add_input_context::<WinScreen>();
+    app.add_input_context::<GameOverScreen>();
+    app.add_observer(start_game);
+    app.add_observer(toggle_wall_rule);
+    app.add_observer(toggle_autopilot);
+    app.add_observer(toggle_game_mode);
+    app.add_observer(cycle_board_size);
+    app.add_observer(widen_board);
+    app.add_observer(narrow_board);
+    app.add_observer(heighten_board);
+    app.add_observer(lower_board);
+    app.add_observer(toggle_seed_mode);
+    app.add_observer(toggle_ghost);
+    app.add_observer(watch_last_run);
+    app.add_observer(continue_saved_game);
+    app.add_observer(leave_end_screen);
+
     app.add_systems(OnEnter(AssetState::Error), spawn_asset_errors);
+    app.add_systems(
+        OnEnter(AppState::Menu),
+        spawn_menu.run_if(not(resource_exists::<RestartRequested>)),
+    );
+    app.add_systems(OnEnter(AppState::Playing), spawn_hud);
+    app.add_systems(
+        OnEnter(AppState::Won),
+        spawn_win_screen.run_if(not(resource_equals(AttractMode(true)))),
+    );
+    app.add_systems(
+        OnEnter(AppState::GameOver),
+        spawn_game_over_screen.run_if(not(resource_equals(AttractMode(true)))),
+    );
+    app.add_systems(
+        Update,
+        (
+            update_wall_rule_text.run_if(resource_changed::<WallRule>),
+            update_autopilot_text.run_if(resource_changed::<Autopilot>),
+            update_game_mode_text.run_if(resource_changed::<GameMode>),
+            update_board_size_text.run_if(resource_changed::<BoardSize>),
+            update_seed_mode_text.run_if(resource_changed::<SeedMode>),
+            update_ghost_text.run_if(resource_changed::<ShowGhost>),
+        ),
+    );
+    app.add_systems(
+        Update,
+        update_hud
+            .run_if(in_state(AppState::Playing))
+            .in_set(AppSystems::Render),
+    );
+}
+
+#[derive(Component)]
+struct MenuScreen;
+
+#[derive(Component)]
+struct WinScreen;
+
+#[derive(Component)]
+struct GameOverScreen;
+
+/// Level, score, length and best score shown while playing.
+#[derive(Component)]
+struct HudText;
+
+/// Text showing the current [`WallRule`], in the menu and in the HUD.
+#[derive(Component)]
+struct WallRuleText;
+
+#[derive(Component)]
+struct AutopilotText;
+
+#[derive(Component)]
+struct GameModeText;
+
+#[derive(Component)]
+struct BoardSizeText;
+
+#[derive(Component)]
+struct SeedModeText;
+
+#[derive(Component)]
+struct GhostText;
+
+/// Offers the saved game, or says why it can't be continued.
+#[derive(Component)]
+struct SavedGameText;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct StartAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ToggleWallRuleAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ToggleAutopilotAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ToggleGameModeAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct CycleBoardSizeAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct WidenBoardAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct NarrowBoardAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct HeightenBoardAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct LowerBoardAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ToggleSeedModeAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ToggleGhostAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct WatchReplayAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ContinueSavedGameAction;
+
+#[derive(InputAction)]
+#[action_output(bool)]
+struct ContinueAction;
+
+fn wall_rule_text(wall_rule: WallRule) -> String {
+    format!("Walls: {}", wall_rule.label())
+}
+
+fn autopilot_text(autopilot: Autopilot) -> String {
+    format!("Autopilot: {}", if autopilot.0 { "on" } else { "off" })
+}
+
+fn game_mode_text(mode: GameMode) -> String {
+    format!("Mode: {}", mode.label())
+}
+
+fn board_size_text(board_size: BoardSize) -> String {
+    format!("Board: {}", board_size.label())
+}
+
+fn seed_mode_text(seed_mode: SeedMode) -> String {
+    format!("Food: {}", seed_mode.label())
+}
+
+fn ghost_text(show_ghost: ShowGhost) -> String {
+    let state = if show_ghost.0 { "on" } else { "off" };
+    format!("Best run ghost (step-synced): {state}")
+}
+
+fn saved_game_text() -> String {
+    if has_saved_game() {
+        "Press 'c' to continue your saved game".to_string()
+    } else {
+        String::new()
+    }
+}
+
+fn spawn_asset_errors(mut commands: Commands, errors: Res<AssetErrors>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: vw(100),
+                height: vh(100),
+                justify_content: JustifyContent::Center,
+                justify_items: JustifyItems::Center,
+                row_gap: px(10),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            children![(
+                Text::new("Failed to load assets"),
+                TextColor(RED.into()),
+                TextLayout::new_with_justify(Justify::Center)
+            )],
+        ))
+        .with_children(|parent| {
+            for error in &errors.0 {
+                parent.spawn((
+                    Text::new(error.clone()),
+                    TextColor(RED.into()),
+                    TextLayout::new_with_justify(Justify::Center),
+                ));
+            }
+        });
+}
+
+fn spawn_menu(
+    mut commands: Commands,
+    wall_rule: Res<WallRule>,
+    autopilot: Res<Autopilot>,
+    mode: Res<GameMode>,
+    board_size: Res<BoardSize>,
+    seed_mode: Res<SeedMode>,
+    show_ghost: Res<ShowGhost>,
+) {
+    commands.spawn((
+        MenuScreen,
+        Node {
+            position_type: PositionType::Absolute,
+            width: vw(100),
+            height: vh(100),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: px(10),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        DespawnOnExit(AppState::Menu),
+        actions!(
+            MenuScreen[
+                (
+                    Action::<StartAction>::new(),
+                    // Enter may still be held from the screen before.
+                    ActionSettings {
+                        require_reset: true,
+                        ..default()
+                    },
+                    bindings![KeyCode::Enter, KeyCode::Space]
+                ),
+                (
+                    Action::<ToggleWallRuleAction>::new(),
+                    bindings![KeyCode::Tab]
+                ),
+                (
+                    Action::<ToggleAutopilotAction>::new(),
+                    bindings![KeyCode::KeyB]
+                ),
+                (
+                    Action::<ToggleGameModeAction>::new(),
+                    bindings![KeyCode::KeyV]
+                ),
+                (
+                    Action::<CycleBoardSizeAction>::new(),
+                    bindings![KeyCode::KeyG]
+                ),
+                (
+                    Action::<WidenBoardAction>::new(),
+                    bindings![KeyCode::ArrowRight]
+                ),
+                (
+                    Action::<NarrowBoardAction>::new(),
+                    bindings![KeyCode::ArrowLeft]
+                ),
+                (
+                    Action::<HeightenBoardAction>::new(),
+                    bindings![KeyCode::ArrowUp]
+                ),
+                (
+                    Action::<LowerBoardAction>::new(),
+                    bindings![KeyCode::ArrowDown]
+                ),
+                (
+                    Action::<ToggleSeedModeAction>::new(),
+                    bindings![KeyCode::KeyD]
+                ),
+                (
+                    Action::<ToggleGhostAction>::new(),
+                    bindings![KeyCode::KeyH]
+                ),
+                (
+                    Action::<WatchReplayAction>::new(),
+                    bindings![KeyCode::KeyR]
+                ),
+                (
+                    Action::<ContinueSavedGameAction>::new(),
+                    bindings![KeyCode::KeyC]
+                ),
+            ]
+        ),
+        children![
+            (Text::new("Snake"), TextFont::from_font_size(64.0)),
+            (WallRuleText, Text::new(wall_rule_text(*wall_rule))),
+            (GameModeText, Text::new(game_mode_text(*mode))),
+            (BoardSizeText, Text::new(board_size_text(*board_size))),
+            (SeedModeText, Text::new(seed_mode_text(*seed_mode))),
+            (GhostText, Text::new(ghost_text(*show_ghost))),
+            (AutopilotText, Text::new(autopilot_text(*autopilot))),
+            Text::new(
+                "Press 'v' for versus, 'g' for the board size, 'd' for the daily challenge, 'tab' to switch walls, 'b' for the autopilot, 'enter' to start"
+            ),
+            Text::new("Press 'h' for the ghost, 'r' to watch your last run"),
+            Text::new("The arrow keys set a custom board size"),
+            Text::new("Versus: player 1 steers with WASD, player 2 with the arrow keys"),
+            (SavedGameText, Text::new(saved_game_text())),
+        ],
+    ));
 }
 
-fn spawn_asset_errors(mut commands: Commands) {
+fn spawn_hud(mut commands: Commands, wall_rule: Res<WallRule>, attract_mode: Res<AttractMode>) {
+    if attract_mode.0 {
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: px(20),
+                width: vw(100),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            DespawnOnExit(AppState::Playing),
+            children![(
+                Text::new("Demo - press any key"),
+                TextFont::from_font_size(32.0)
+            )],
+        ));
+    }
+
     commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(10),
+            left: px(10),
+            row_gap: px(5),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        DespawnOnExit(AppState::Playing),
+        children![
+            (HudText, Text::default()),
+            (WallRuleText, Text::new(wall_rule_text(*wall_rule))),
+        ],
+    ));
+}
+
+fn update_hud(
+    score: Res<Score>,
+    high_score: Res<HighScore>,
+    board: Res<Board>,
+    level: Res<CurrentLevel>,
+    mut text: Single<&mut Text, With<HudText>>,
+) {
+    let content = match board.snakes() {
+        [snake] => format!(
+            "Level: {}   Score: {}   Length: {}   Best: {}",
+            level.number(),
+            score.0,
+            snake.length(),
+            high_score.0.max(score.0)
+        ),
+        snakes => {
+            let lengths: Vec<String> = snakes
+                .iter()
+                .enumerate()
+                .map(|(player, snake)| format!("Player {}: {}", player + 1, snake.length()))
+                .collect();
+            format!("Level: {}   {}", level.number(), lengths.join("   "))
+        }
+    };
+    text.set_if_neq(Text::new(content));
+}
+
+fn update_wall_rule_text(
+    wall_rule: Res<WallRule>,
+    mut texts: Query<&mut Text, With<WallRuleText>>,
+) {
+    for mut text in &mut texts {
+        text.0 = wall_rule_text(*wall_rule);
+    }
+}
+
+fn update_autopilot_text(
+    autopilot: Res<Autopilot>,
+    mut texts: Query<&mut Text, With<AutopilotText>>,
+) {
+    for mut text in &mut texts {
+        text.0 = autopilot_text(*autopilot);
+    }
+}
+
+fn update_game_mode_text(mode: Res<GameMode>, mut texts: Query<&mut Text, With<GameModeText>>) {
+    for mut text in &mut texts {
+        text.0 = game_mode_text(*mode);
+    }
+}
+
+fn update_board_size_text(
+    board_size: Res<BoardSize>,
+    mut texts: Query<&mut Text, With<BoardSizeText>>,
+) {
+    for mut text in &mut texts {
+        text.0 = board_size_text(*board_size);
+    }
+}
+
+fn update_seed_mode_text(
+    seed_mode: Res<SeedMode>,
+    mut texts: Query<&mut Text, With<SeedModeText>>,
+) {
+    for mut text in &mut texts {
+        text.0 = seed_mode_text(*seed_mode);
+    }
+}
+
+fn update_ghost_text(show_ghost: Res<ShowGhost>, mut texts: Query<&mut Text, With<GhostText>>) {
+    for mut text in &mut texts {
+        text.0 = ghost_text(*show_ghost);
+    }
+}
+
+fn start_game(_event: On<Start<StartAction>>, mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Playing);
+}
+
+fn toggle_wall_rule(_event: On<Start<ToggleWallRuleAction>>, mut wall_rule: ResMut<WallRule>) {
+    *wall_rule = wall_rule.toggled();
+}
+
+fn toggle_autopilot(_event: On<Start<ToggleAutopilotAction>>, mut autopilot: ResMut<Autopilot>) {
+    autopilot.0 = !autopilot.0;
+}
+
+fn toggle_game_mode(_event: On<Start<ToggleGameModeAction>>, mut mode: ResMut<GameMode>) {
+    *mode = mode.toggled();
+}
+
+fn cycle_board_size(_event: On<Start<CycleBoardSizeAction>>, mut board_size: ResMut<BoardSize>) {
+    *board_size = board_size.next();
+}
+
+fn widen_board(_event: On<Start<WidenBoardAction>>, mut board_size: ResMut<BoardSize>) {
+    *board_size = board_size.resized(1, 0);
+}
+
+fn narrow_board(_event: On<Start<NarrowBoardAction>>, mut board_size: ResMut<BoardSize>) {
+    *board_size = board_size.resized(-1, 0);
+}
+
+fn heighten_board(_event: On<Start<HeightenBoardAction>>, mut board_size: ResMut<BoardSize>) {
+    *board_size = board_size.resized(0, 1);
+}
+
+fn lower_board(_event: On<Start<LowerBoardAction>>, mut board_size: ResMut<BoardSize>) {
+    *board_size = board_size.resized(0, -1);
+}
+
+fn toggle_seed_mode(_event: On<Start<ToggleSeedModeAction>>, mut seed_mode: ResMut<SeedMode>) {
+    *seed_mode = seed_mode.toggled();
+}
+
+fn toggle_ghost(_event: On<Start<ToggleGhostAction>>, mut show_ghost: ResMut<ShowGhost>) {
+    show_ghost.0 = !show_ghost.0;
+}
+
+fn watch_last_run(
+    _event: On<Start<WatchReplayAction>>,
+    mut commands: Commands,
+    mut mode: ResMut<GameMode>,
+    mut wall_rule: ResMut<WallRule>,
+    mut board_size: ResMut<BoardSize>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    match Playback::start(LAST_RUN_PATH, &mut mode, &mut wall_rule, &mut board_size) {
+        Ok(playback) => {
+            commands.insert_resource(playback);
+            next_state.set(AppState::Playing);
+        }
+        Err(error) => warn!("Can't watch the last run: {error}"),
+    }
+}
+
+fn continue_saved_game(
+    _event: On<Start<ContinueSavedGameAction>>,
+    mut commands: Commands,
+    mut mode: ResMut<GameMode>,
+    mut wall_rule: ResMut<WallRule>,
+    mut board_size: ResMut<BoardSize>,
+    mut texts: Query<(&mut Text, &mut TextColor), With<SavedGameText>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    match ResumedGame::start(&mut mode, &mut wall_rule, &mut board_size) {
+        Ok(resumed) => {
+            commands.insert_resource(resumed);
+            next_state.set(AppState::Playing);
+        }
+        Err(error) => {
+            warn!("Can't continue the saved game: {error}");
+            for (mut text, mut color) in &mut texts {
+                text.0 = format!("Can't continue the saved game: {error}");
+                color.0 = RED.into();
+            }
+        }
+    }
+}
+
+fn spawn_win_screen(mut commands: Commands) {
+    commands.spawn((
+        WinScreen,
         Node {
             position_type: PositionType::Absolute,
             width: vw(100),
             height: vh(100),
             justify_content: JustifyContent::Center,
-            justify_items: JustifyItems::Center,
+            align_items: AlignItems::Center,
             row_gap: px(10),
             flex_direction: FlexDirection::Column,
             ..default()
         },
-        children![(
-            Text::new("Failed to load assets"),
-            TextColor(RED.into()),
-            TextLayout::new_with_justify(Justify::Center)
-        )],
+        DespawnOnExit(AppState::Won),
+        actions!(
+            WinScreen[(
+                Action::<ContinueAction>::new(),
+                // A key held when the board filled up shouldn't skip the screen.
+                ActionSettings {
+                    require_reset: true,
+                    ..default()
+                },
+                bindings![KeyCode::Enter, KeyCode::Space]
+            )]
+        ),
+        children![
+            (Text::new("Board cleared!"), TextFont::from_font_size(64.0)),
+            Text::new("Press 'enter' to go back to the menu"),
+        ],
     ));
 }
+
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    info: Res<GameOverInfo>,
+    high_score: Res<HighScore>,
+    rng: Res<GameRng>,
+) {
+    let (title, lines) = match *info {
+        GameOverInfo::Solo {
+            reason,
+            length,
+            score,
+        } => (
+            "Game over".to_string(),
+            vec![
+                reason.describe().to_string(),
+                format!(
+                    "Length: {length}   Score: {score}   Best: {}",
+                    high_score.0.max(score)
+                ),
+            ],
+        ),
+        GameOverInfo::Versus {
+            winner: Some(winner),
+            reason,
+        } => (
+            format!("{} wins!", winner.name()),
+            vec![format!(
+                "{} {}",
+                Player(1 - winner.0).name(),
+                reason.summary()
+            )],
+        ),
+        GameOverInfo::Versus { winner: None, .. } => {
+            ("Draw".to_string(), vec!["Both snakes crashed".to_string()])
+        }
+    };
+
+    commands
+        .spawn((
+            GameOverScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                width: vw(100),
+                height: vh(100),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: px(10),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            DespawnOnExit(AppState::GameOver),
+            actions!(
+                GameOverScreen[(
+                    Action::<ContinueAction>::new(),
+                    // A key held when the snake died shouldn't skip the screen.
+                    ActionSettings {
+                        require_reset: true,
+                        ..default()
+                    },
+                    bindings![KeyCode::Enter, KeyCode::Space]
+                )]
+            ),
+            children![(Text::new(title), TextFont::from_font_size(64.0))],
+        ))
+        .with_children(|parent| {
+            for line in lines {
+                parent.spawn(Text::new(line));
+            }
+            parent.spawn(Text::new(format!(
+                "Seed: {} (replay with --seed {0})",
+                rng.seed
+            )));
+            parent.spawn(Text::new("Press 'enter' to go back to the menu"));
+        });
+}
+
+/// Leaves the win or game over screen.
+fn leave_end_screen(
+    _event: On<Start<ContinueAction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    next_state.set(AppState::Menu);
+}